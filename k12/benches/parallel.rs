@@ -0,0 +1,49 @@
+//! Serial against parallel hashing of a few leaves, to find the crossover
+//! points `MIN_GROUPS_PER_JOB` and `MIN_GROUPS_PER_THREAD` for each
+//! backend. Run with `--features parallel` or `--features threads`, and
+//! divide the number of leaves where `parallel_*` overtakes `serial_*` by
+//! the number of leaves the backend hashes in lockstep.
+#![cfg(any(feature = "parallel", feature = "threads"))]
+#![feature(test)]
+extern crate test;
+
+use digest::Update;
+use k12::{KangarooTwelve, CHUNK_SIZE};
+use test::{black_box, Bencher};
+
+/// Hash `leaves` whole leaves after the first chunk on up to `threads`
+/// threads, or as many as available if that is 0.
+fn hash_leaves(b: &mut Bencher, leaves: usize, threads: usize) {
+    let data = vec![0x42; (leaves + 1) * CHUNK_SIZE];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut hasher = KangarooTwelve::new().with_threads(threads);
+        hasher.update(&data);
+        black_box(hasher)
+    });
+}
+
+macro_rules! crossover {
+    ($($serial:ident $parallel:ident $leaves:expr;)*) => {
+        $(
+            #[bench]
+            fn $serial(b: &mut Bencher) {
+                hash_leaves(b, $leaves, 1);
+            }
+
+            #[bench]
+            fn $parallel(b: &mut Bencher) {
+                hash_leaves(b, $leaves, 0);
+            }
+        )*
+    };
+}
+
+crossover!(
+    serial_4 parallel_4 4;
+    serial_8 parallel_8 8;
+    serial_16 parallel_16 16;
+    serial_32 parallel_32 32;
+    serial_64 parallel_64 64;
+    serial_128 parallel_128 128;
+);
@@ -0,0 +1,173 @@
+//! Keccak-p[1600, 12] on two interleaved states using NEON, or the Armv8.2
+//! SHA3 instructions with the `arm-sha3` feature when the CPU supports them.
+
+#![allow(unsafe_code)]
+
+use crate::{lanes::RC, multi_buffer, CHAINING_VALUE_SIZE};
+use core::arch::aarch64::*;
+
+#[cfg(feature = "arm-sha3")]
+cpufeatures::new!(sha3_cpuid, "sha3");
+
+/// Whether the CPU supports the SHA3 instructions, detected on first use.
+#[cfg(feature = "arm-sha3")]
+pub(crate) fn has_sha3() -> bool {
+    sha3_cpuid::get()
+}
+
+/// Hash 2 whole chunks as leaves in lockstep with the SHA3 instructions,
+/// writing their chaining values to `cvs`.
+///
+/// # Panics
+///
+/// If the CPU does not support the SHA3 instructions.
+#[cfg(feature = "arm-sha3")]
+pub(crate) fn hash_leaves_x2_sha3(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    assert!(has_sha3());
+    // SAFETY: SHA3 support was just checked
+    multi_buffer::hash_leaves_with(chunks, cvs, |lanes| unsafe { keccak_x2_sha3(lanes) });
+}
+
+/// Hash 2 whole chunks as leaves in lockstep with NEON, writing their
+/// chaining values to `cvs`.
+pub(crate) fn hash_leaves_x2_neon(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    // SAFETY: NEON is enabled at compile time
+    multi_buffer::hash_leaves_with(chunks, cvs, |lanes| unsafe { keccak_x2_neon(lanes) });
+}
+
+/// Define a Keccak-p[1600, 12] on two interleaved states from the SHA3
+/// instructions, or functions with the same semantics.
+macro_rules! keccak_x2 {
+    ($name:ident, $feature:tt, $xor3:ident, $rax1:ident, $xar:ident, $bcax:ident) => {
+        #[target_feature(enable = $feature)]
+        unsafe fn $name(lanes: &mut [[u64; 2]; 25]) {
+            let mut a = [vdupq_n_u64(0); 25];
+            for (a, lane) in a.iter_mut().zip(lanes.iter()) {
+                *a = vld1q_u64(lane.as_ptr());
+            }
+
+            let mut b = [vdupq_n_u64(0); 25];
+            let mut c = [vdupq_n_u64(0); 5];
+            let mut d = [vdupq_n_u64(0); 5];
+            let (mut x, mut y): (usize, usize);
+
+            for &rc in RC.iter() {
+                // θ
+                FOR5!(x, 1, {
+                    c[x] = $xor3($xor3(a[x], a[x + 5], a[x + 10]), a[x + 15], a[x + 20]);
+                });
+                FOR5!(x, 1, {
+                    d[x] = $rax1(c[(x + 4) % 5], c[(x + 1) % 5]);
+                });
+
+                // θ applied while moving each lane to its place for ρ and π. XAR
+                // rotates right, so the immediates are 64 minus the ρ offsets.
+                b[0] = veorq_u64(a[0], d[0]);
+                b[1] = $xar::<20>(a[6], d[1]);
+                b[2] = $xar::<21>(a[12], d[2]);
+                b[3] = $xar::<43>(a[18], d[3]);
+                b[4] = $xar::<50>(a[24], d[4]);
+                b[5] = $xar::<36>(a[3], d[3]);
+                b[6] = $xar::<44>(a[9], d[4]);
+                b[7] = $xar::<61>(a[10], d[0]);
+                b[8] = $xar::<19>(a[16], d[1]);
+                b[9] = $xar::<3>(a[22], d[2]);
+                b[10] = $xar::<63>(a[1], d[1]);
+                b[11] = $xar::<58>(a[7], d[2]);
+                b[12] = $xar::<39>(a[13], d[3]);
+                b[13] = $xar::<56>(a[19], d[4]);
+                b[14] = $xar::<46>(a[20], d[0]);
+                b[15] = $xar::<37>(a[4], d[4]);
+                b[16] = $xar::<28>(a[5], d[0]);
+                b[17] = $xar::<54>(a[11], d[1]);
+                b[18] = $xar::<49>(a[17], d[2]);
+                b[19] = $xar::<8>(a[23], d[3]);
+                b[20] = $xar::<2>(a[2], d[2]);
+                b[21] = $xar::<9>(a[8], d[3]);
+                b[22] = $xar::<25>(a[14], d[4]);
+                b[23] = $xar::<23>(a[15], d[0]);
+                b[24] = $xar::<62>(a[21], d[1]);
+
+                // χ, with BCAX computing a ^ (b & !c)
+                FOR5!(y, 5, {
+                    FOR5!(x, 1, {
+                        a[x + y] = $bcax(b[x + y], b[(x + 2) % 5 + y], b[(x + 1) % 5 + y]);
+                    });
+                });
+
+                // ι
+                a[0] = veorq_u64(a[0], vdupq_n_u64(rc));
+            }
+
+            for (a, lane) in a.iter().zip(lanes.iter_mut()) {
+                vst1q_u64(lane.as_mut_ptr(), *a);
+            }
+        }
+    };
+}
+
+#[cfg(feature = "arm-sha3")]
+keccak_x2!(
+    keccak_x2_sha3,
+    "sha3",
+    veor3q_u64,
+    vrax1q_u64,
+    vxarq_u64,
+    vbcaxq_u64
+);
+keccak_x2!(keccak_x2_neon, "neon", xor3, rax1, xar, bcax);
+
+// The SHA3 instructions in terms of NEON
+
+#[inline(always)]
+unsafe fn xor3(a: uint64x2_t, b: uint64x2_t, c: uint64x2_t) -> uint64x2_t {
+    veorq_u64(veorq_u64(a, b), c)
+}
+
+#[inline(always)]
+unsafe fn rax1(a: uint64x2_t, b: uint64x2_t) -> uint64x2_t {
+    veorq_u64(a, vsriq_n_u64::<63>(vshlq_n_u64::<1>(b), b))
+}
+
+#[inline(always)]
+unsafe fn xar<const IMM6: i32>(a: uint64x2_t, b: uint64x2_t) -> uint64x2_t {
+    let x = veorq_u64(a, b);
+    let left = vdupq_n_s64(i64::from(64 - IMM6));
+    let right = vdupq_n_s64(-i64::from(IMM6));
+    vorrq_u64(vshlq_u64(x, left), vshlq_u64(x, right))
+}
+
+#[inline(always)]
+unsafe fn bcax(a: uint64x2_t, b: uint64x2_t, c: uint64x2_t) -> uint64x2_t {
+    veorq_u64(a, vbicq_u64(b, c))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{backend::HashLeaves, leaf_chaining_value, CHAINING_VALUE_SIZE, CHUNK_SIZE};
+
+    fn check(hash_leaves: HashLeaves) {
+        let mut input = [0u8; 2 * CHUNK_SIZE];
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+        let mut cvs = [[0u8; CHAINING_VALUE_SIZE]; 2];
+        hash_leaves(&input, &mut cvs);
+        for (chunk, cv) in input.chunks_exact(CHUNK_SIZE).zip(&cvs) {
+            assert_eq!(*cv, leaf_chaining_value(chunk));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "arm-sha3")]
+    fn sha3_matches_single_leaf() {
+        if super::has_sha3() {
+            check(super::hash_leaves_x2_sha3);
+        }
+    }
+
+    #[test]
+    fn neon_matches_single_leaf() {
+        check(super::hash_leaves_x2_neon);
+    }
+}
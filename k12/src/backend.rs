@@ -0,0 +1,186 @@
+//! Choice between the implementations of hashing whole leaves.
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+use crate::aarch64;
+#[cfg(target_feature = "avx512f")]
+use crate::multi_buffer;
+#[cfg(feature = "simd")]
+use crate::simd;
+#[cfg(all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")))]
+use crate::x86;
+use crate::{leaf_chaining_value, CHAINING_VALUE_SIZE, CHUNK_SIZE};
+use core::{
+    fmt, mem,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Signature of the functions hashing leaves in lockstep.
+pub(crate) type HashLeaves = fn(&[u8], &mut [[u8; CHAINING_VALUE_SIZE]]);
+
+/// An implementation of hashing whole leaves. All but the scalar one hash
+/// several leaves in lockstep, interleaving their states in SIMD registers.
+///
+/// The fastest available backend is used by default. Another can be chosen
+/// with [`KangarooTwelve::with_backend`](crate::KangarooTwelve::with_backend),
+/// e.g. to compare them or to pin one that has been validated.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Backend {
+    /// One leaf at a time.
+    Scalar,
+    /// Two leaves at a time with SSE2, with the `avx2` feature.
+    Sse2,
+    /// Four leaves at a time with AVX2, with the `avx2` feature.
+    Avx2,
+    /// Eight leaves at a time with AVX-512F, enabled at compile time or
+    /// detected at runtime with the `avx512` feature.
+    Avx512,
+    /// Two leaves at a time with NEON, with the `neon` feature.
+    Neon,
+    /// Two leaves at a time with the Armv8.2 SHA3 instructions, with the
+    /// `arm-sha3` feature.
+    Sha3,
+    /// Two leaves at a time with `core::simd`, with the `simd` feature.
+    Simd,
+}
+
+impl Backend {
+    /// All backends, from fastest to slowest on the CPUs supporting them.
+    const ALL: [Backend; 7] = [
+        Backend::Avx512,
+        Backend::Avx2,
+        Backend::Sse2,
+        Backend::Sha3,
+        Backend::Neon,
+        Backend::Simd,
+        Backend::Scalar,
+    ];
+
+    /// The fastest backend available, chosen on first use, or the scalar
+    /// one with the `force-soft` feature.
+    pub fn detect() -> Self {
+        static DETECTED: AtomicU8 = AtomicU8::new(u8::MAX);
+
+        if cfg!(feature = "force-soft") {
+            return Backend::Scalar;
+        }
+        if let Some(&backend) = Self::ALL.get(usize::from(DETECTED.load(Ordering::Relaxed))) {
+            return backend;
+        }
+        let index = Self::ALL
+            .iter()
+            .position(|backend| backend.is_available())
+            .unwrap_or(Self::ALL.len() - 1);
+        DETECTED.store(index as u8, Ordering::Relaxed);
+        Self::ALL[index]
+    }
+
+    /// Whether this backend is compiled in and supported by the CPU.
+    ///
+    /// The `force-soft` feature only changes the default, so backends it
+    /// disables are still available when chosen explicitly.
+    pub fn is_available(self) -> bool {
+        self == Backend::Scalar || self.group().is_some()
+    }
+
+    /// The available backends, from fastest to slowest.
+    pub fn available() -> impl Iterator<Item = Backend> {
+        Self::ALL
+            .iter()
+            .copied()
+            .filter(|backend| backend.is_available())
+    }
+
+    /// Number of leaves this backend hashes in lockstep, or 1 if it is
+    /// unavailable.
+    #[cfg(any(feature = "parallel", feature = "threads"))]
+    pub(crate) fn degree(self) -> usize {
+        self.group().map_or(1, |(degree, _)| degree)
+    }
+
+    /// How many leaves this backend hashes at a time, which divides
+    /// [`LEAF_DEGREE`](crate::LEAF_DEGREE), and the function doing so.
+    fn group(self) -> Option<(usize, HashLeaves)> {
+        match self {
+            #[cfg(all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::Sse2 if x86::has_sse2() => Some((2, x86::hash_leaves_x2)),
+            #[cfg(all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::Avx2 if x86::has_avx2() => Some((4, x86::hash_leaves_x4)),
+            #[cfg(all(feature = "avx512", any(target_arch = "x86", target_arch = "x86_64")))]
+            Backend::Avx512 if x86::has_avx512() => Some((8, x86::hash_leaves_x8)),
+            #[cfg(target_feature = "avx512f")]
+            Backend::Avx512 => Some((8, multi_buffer::hash_leaves::<8>)),
+            #[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+            Backend::Neon => Some((2, aarch64::hash_leaves_x2_neon)),
+            #[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "arm-sha3"))]
+            Backend::Sha3 if aarch64::has_sha3() => Some((2, aarch64::hash_leaves_x2_sha3)),
+            #[cfg(feature = "simd")]
+            Backend::Simd => Some((2, simd::hash_leaves_x2)),
+            _ => None,
+        }
+    }
+
+    /// Hash each whole chunk of `chunks` as a leaf, writing its chaining
+    /// value to the corresponding element of `cvs`.
+    ///
+    /// As many groups of leaves as possible are hashed in lockstep, and any
+    /// remaining ones one at a time.
+    pub(crate) fn hash_leaves(self, mut chunks: &[u8], mut cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+        if let Some((degree, hash_group)) = self.group() {
+            while cvs.len() >= degree {
+                let (group, rest) = chunks.split_at(degree * CHUNK_SIZE);
+                let (group_cvs, rest_cvs) = mem::take(&mut cvs).split_at_mut(degree);
+                hash_group(group, group_cvs);
+                chunks = rest;
+                cvs = rest_cvs;
+            }
+        }
+
+        for (chunk, cv) in chunks.chunks_exact(CHUNK_SIZE).zip(cvs) {
+            *cv = leaf_chaining_value(chunk);
+        }
+    }
+}
+
+/// Error choosing a [`Backend`] that is not compiled in or not supported by
+/// the CPU.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UnsupportedBackend(pub Backend);
+
+impl fmt::Display for UnsupportedBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the {:?} backend is not available", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnsupportedBackend {}
+
+#[cfg(test)]
+mod tests {
+    use super::Backend;
+    use crate::{CHAINING_VALUE_SIZE, CHUNK_SIZE};
+
+    #[test]
+    fn backends_match_scalar() {
+        let mut input = [0u8; 11 * CHUNK_SIZE];
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+        let mut expected = [[0u8; CHAINING_VALUE_SIZE]; 11];
+        Backend::Scalar.hash_leaves(&input, &mut expected);
+
+        assert!(Backend::detect().is_available());
+        for backend in Backend::available() {
+            let mut cvs = [[0u8; CHAINING_VALUE_SIZE]; 11];
+            backend.hash_leaves(&input, &mut cvs);
+            assert_eq!(cvs, expected, "{:?}", backend);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "force-soft")]
+    fn force_soft() {
+        assert_eq!(Backend::detect(), Backend::Scalar);
+    }
+}
@@ -0,0 +1,408 @@
+//! Retained KangarooTwelve trees, for checking chunks of a message against
+//! its digest.
+
+use crate::{
+    chaining_values, leaf_chaining_value, tree,
+    turbo_shake::{Sponge128, RATE, ROUNDS},
+    ChunkError, KangarooTwelve, Reader, CHAINING_VALUE_SIZE, CHUNK_SIZE, CHUNK_SIZE_U64,
+};
+use core::{cmp::min, mem};
+use digest::{ExtendableOutput, Update, XofReader};
+use std::{vec, vec::Vec};
+
+/// Size of the shortest digest a tree is checked against, for 128-bit
+/// security.
+const MIN_DIGEST_SIZE: usize = 32;
+
+/// The KangarooTwelve tree of a message, from which any chunk of the
+/// message can be checked against the digest on its own.
+///
+/// The message is cut into chunks of [`CHUNK_SIZE`] bytes like for
+/// [`ConcurrentHasher`](crate::ConcurrentHasher). The tree keeps copies of
+/// the first and last chunks, and the chaining values of the others, which
+/// is all the final node of KangarooTwelve is computed from. As that tree
+/// has a single level, this is also the smallest proof that a chunk belongs
+/// to a digest, and one proof for all chunks at once: for a large message,
+/// 32 bytes per chunk, or 0.4 %, plus up to two chunks.
+///
+/// A receiver of a large message can thus fetch the tree, check it once
+/// against a trusted digest with [`verify_digest`](Self::verify_digest),
+/// then check the chunks with [`verify_chunk`](Self::verify_chunk) as they
+/// arrive, in any order, without buffering the message.
+///
+/// ```
+/// use k12::{digest::ExtendableOutput, digest::Update, ChunkTree, KangarooTwelve, CHUNK_SIZE};
+///
+/// let message = vec![0x42; 5 * CHUNK_SIZE + 123];
+/// let digest = KangarooTwelve::new().chain(&message).finalize_boxed(32);
+///
+/// // The sender publishes the tree...
+/// let tree = ChunkTree::new(&message);
+///
+/// // ...which the receiver checks against the digest, then the chunks
+/// assert!(tree.verify_digest(&digest));
+/// let chunk = message.chunks(CHUNK_SIZE).nth(3).unwrap();
+/// assert!(tree.verify_chunk(3, chunk).is_ok());
+/// assert!(tree.verify_chunk(3, &[0; CHUNK_SIZE]).is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChunkTree<'cs> {
+    customization: &'cs [u8],
+    message_len: u64,
+
+    /// Total number of chunks, at least 1 even for an empty message
+    chunks: u64,
+
+    first: Vec<u8>,
+
+    /// Chaining values of the chunks between the first and the last one
+    cvs: Vec<[u8; CHAINING_VALUE_SIZE]>,
+
+    /// Last chunk, empty if it is also the first one
+    last: Vec<u8>,
+}
+
+impl<'cs> ChunkTree<'cs> {
+    /// Compute the tree of `message`.
+    pub fn new(message: &[u8]) -> Self {
+        Self::new_with_customization(message, &[])
+    }
+
+    /// Compute the tree of `message` with the given customization.
+    pub fn new_with_customization(message: &[u8], customization: &'cs [u8]) -> Self {
+        let message_len = message.len() as u64;
+        let chunks = chunk_count(message_len);
+        let mut cvs = vec![[0u8; CHAINING_VALUE_SIZE]; chunks.saturating_sub(2) as usize];
+        let (first, last) = if chunks == 1 {
+            (message, &[][..])
+        } else {
+            let last_start = (chunks as usize - 1) * CHUNK_SIZE;
+            chaining_values(&message[CHUNK_SIZE..last_start], &mut cvs);
+            (&message[..CHUNK_SIZE], &message[last_start..])
+        };
+        Self {
+            customization,
+            message_len,
+            chunks,
+            first: first.to_vec(),
+            cvs,
+            last: last.to_vec(),
+        }
+    }
+
+    /// Assemble the tree of a message of `message_len` bytes from its
+    /// parts, e.g. received from the holder of the message.
+    ///
+    /// `last` must be empty if the message has a single chunk. An error is
+    /// returned if a chunk has the wrong length, if chaining values are
+    /// missing, or for the first chunk that is given a chaining value
+    /// although it must be copied: the last one, or a chunk past the end.
+    pub fn from_parts(
+        message_len: u64,
+        customization: &'cs [u8],
+        first: Vec<u8>,
+        cvs: Vec<[u8; CHAINING_VALUE_SIZE]>,
+        last: Vec<u8>,
+    ) -> Result<Self, ChunkError> {
+        let chunks = chunk_count(message_len);
+        if first.len() as u64 != chunk_len(message_len, chunks, 0) {
+            return Err(ChunkError::InvalidLength { index: 0 });
+        }
+        let middle = chunks.saturating_sub(2);
+        match (cvs.len() as u64).checked_sub(middle) {
+            None => {
+                let index = cvs.len() as u64 + 1;
+                return Err(ChunkError::Missing { index });
+            }
+            Some(0) => {}
+            Some(_) => {
+                let index = middle + 1;
+                return Err(ChunkError::OutOfRange { index });
+            }
+        }
+        let last_len = match chunks {
+            1 => 0,
+            _ => chunk_len(message_len, chunks, chunks - 1),
+        };
+        if last.len() as u64 != last_len {
+            return Err(ChunkError::InvalidLength { index: chunks - 1 });
+        }
+        Ok(Self {
+            customization,
+            message_len,
+            chunks,
+            first,
+            cvs,
+            last,
+        })
+    }
+
+    /// Number of chunks the message is cut into.
+    pub fn chunk_count(&self) -> u64 {
+        self.chunks
+    }
+
+    /// Length of the message in bytes.
+    pub fn message_len(&self) -> u64 {
+        self.message_len
+    }
+
+    /// Customization string of the digest.
+    pub fn customization(&self) -> &'cs [u8] {
+        self.customization
+    }
+
+    /// Copy of the first chunk of the message.
+    pub fn first_chunk(&self) -> &[u8] {
+        &self.first
+    }
+
+    /// Chaining values of the chunks between the first and the last one.
+    pub fn chaining_values(&self) -> &[[u8; CHAINING_VALUE_SIZE]] {
+        &self.cvs
+    }
+
+    /// Copy of the last chunk of the message, empty if it is also the first
+    /// one.
+    pub fn last_chunk(&self) -> &[u8] {
+        &self.last
+    }
+
+    /// Check that chunk `index` of the message is `chunk`.
+    pub fn verify_chunk(&self, index: u64, chunk: &[u8]) -> Result<(), ChunkError> {
+        if index >= self.chunks {
+            return Err(ChunkError::OutOfRange { index });
+        }
+        if chunk.len() as u64 != self.chunk_len(index) {
+            return Err(ChunkError::InvalidLength { index });
+        }
+        let matches = match self.copy(index) {
+            Some(copy) => chunk == copy,
+            None => leaf_chaining_value(chunk) == self.cvs[index as usize - 1],
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(ChunkError::Mismatch { index })
+        }
+    }
+
+    /// Check the message as it is streamed, from its start.
+    pub fn verifier(&self) -> ChunkVerifier<'_, 'cs> {
+        ChunkVerifier {
+            tree: self,
+            index: 0,
+            pos: 0,
+            leaf: Sponge128::new(),
+            error: None,
+        }
+    }
+
+    /// Check the message as it is streamed, from the start of chunk
+    /// `index`, e.g. to resume an interrupted download.
+    pub fn verifier_at(&self, index: u64) -> Result<ChunkVerifier<'_, 'cs>, ChunkError> {
+        if index >= self.chunks {
+            return Err(ChunkError::OutOfRange { index });
+        }
+        Ok(ChunkVerifier {
+            index,
+            ..self.verifier()
+        })
+    }
+
+    /// Retrieve the output stream of the KangarooTwelve digest of the
+    /// message, computed from the tree.
+    pub fn finalize_xof(&self) -> Reader {
+        let mut h = KangarooTwelve::new_with_customization(self.customization);
+        h.update(&self.first);
+        if self.chunks > 1 {
+            h.absorb_chaining_values(&self.cvs);
+            h.update(&self.last);
+        }
+        h.finalize_xof()
+    }
+
+    /// Check that the tree is that of the message of the digest `digest`,
+    /// of any length from 32 bytes.
+    ///
+    /// Shorter digests are always rejected, as they would let a forged tree
+    /// through by chance.
+    pub fn verify_digest(&self, digest: &[u8]) -> bool {
+        if digest.len() < MIN_DIGEST_SIZE {
+            return false;
+        }
+        let mut reader = self.finalize_xof();
+        let mut block = [0u8; 64];
+        digest.chunks(block.len()).all(|expected| {
+            let block = &mut block[..expected.len()];
+            reader.read(block);
+            block == expected
+        })
+    }
+}
+
+impl ChunkTree<'_> {
+    /// Length of chunk `index`.
+    fn chunk_len(&self, index: u64) -> u64 {
+        chunk_len(self.message_len, self.chunks, index)
+    }
+
+    /// Copy of chunk `index` if it is kept whole rather than as a chaining
+    /// value.
+    fn copy(&self, index: u64) -> Option<&[u8]> {
+        if index == 0 {
+            Some(&self.first)
+        } else if index + 1 == self.chunks {
+            Some(&self.last)
+        } else {
+            None
+        }
+    }
+}
+
+/// Checker of a message streamed in order against its [`ChunkTree`],
+/// rejecting each chunk as soon as it is known not to match.
+///
+/// Chunks the tree keeps copies of are compared byte for byte as they
+/// arrive, and the others are hashed as they arrive and compared to their
+/// chaining value once complete, so no more than a chunk of the message
+/// is accepted before tampering is detected, and none of it is buffered.
+/// Once an error is returned, the verifier returns it again on every call.
+///
+/// ```
+/// use k12::{ChunkTree, CHUNK_SIZE};
+///
+/// let message = vec![0x42; 5 * CHUNK_SIZE + 123];
+/// let tree = ChunkTree::new(&message);
+///
+/// let mut verifier = tree.verifier();
+/// for part in message.chunks(1000) {
+///     verifier.update(part).unwrap();
+/// }
+/// verifier.finish().unwrap();
+///
+/// // Resume after the first three chunks, then receive a corrupted chunk
+/// let mut verifier = tree.verifier_at(3).unwrap();
+/// verifier.update(&message[3 * CHUNK_SIZE..4 * CHUNK_SIZE]).unwrap();
+/// assert!(verifier.update(&[0; CHUNK_SIZE]).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChunkVerifier<'t, 'cs> {
+    tree: &'t ChunkTree<'cs>,
+
+    /// Index of the current chunk, or the number of chunks at the end
+    index: u64,
+
+    /// Number of bytes received of the current chunk
+    pos: usize,
+
+    /// Leaf of the current chunk, if it is checked by chaining value
+    leaf: Sponge128,
+
+    error: Option<ChunkError>,
+}
+
+impl ChunkVerifier<'_, '_> {
+    /// Check the next bytes of the message.
+    pub fn update(&mut self, data: &[u8]) -> Result<(), ChunkError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let result = self.check(data);
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+        result
+    }
+
+    fn check(&mut self, mut data: &[u8]) -> Result<(), ChunkError> {
+        let chunks = self.tree.chunks;
+        while !data.is_empty() {
+            let index = self.index;
+            if index == chunks {
+                return Err(ChunkError::OutOfRange { index });
+            }
+            let len = self.tree.chunk_len(index) as usize;
+            let (bytes, rest) = data.split_at(min(len - self.pos, data.len()));
+            match self.tree.copy(index) {
+                Some(copy) if copy[self.pos..self.pos + bytes.len()] != *bytes => {
+                    return Err(ChunkError::Mismatch { index });
+                }
+                Some(_) => {}
+                None => self.leaf.absorb(bytes),
+            }
+            self.pos += bytes.len();
+            if self.pos == len {
+                if self.tree.copy(index).is_none() {
+                    let leaf = mem::take(&mut self.leaf);
+                    let cv = tree::chaining_value::<RATE, CHAINING_VALUE_SIZE, ROUNDS>(leaf);
+                    if cv != self.tree.cvs[index as usize - 1] {
+                        return Err(ChunkError::Mismatch { index });
+                    }
+                }
+                self.index += 1;
+                self.pos = 0;
+            }
+            data = rest;
+        }
+        Ok(())
+    }
+
+    /// Index of the chunk being received, from the start of which the
+    /// verifier can be recreated with [`ChunkTree::verifier_at`].
+    pub fn chunk_index(&self) -> u64 {
+        self.index
+    }
+
+    /// Offset in the message of the next byte expected.
+    pub fn position(&self) -> u64 {
+        // Past the last chunk, which may be shorter
+        min(
+            self.index * CHUNK_SIZE_U64 + self.pos as u64,
+            self.tree.message_len,
+        )
+    }
+
+    /// Check that the whole rest of the message has been received.
+    pub fn finish(self) -> Result<(), ChunkError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if self.position() == self.tree.message_len {
+            Ok(())
+        } else {
+            Err(ChunkError::Missing { index: self.index })
+        }
+    }
+}
+
+/// Chunk errors are reported as [`std::io::ErrorKind::InvalidData`].
+impl std::io::Write for ChunkVerifier<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Number of chunks of a message of `message_len` bytes.
+fn chunk_count(message_len: u64) -> u64 {
+    match message_len {
+        0 => 1,
+        len => (len - 1) / CHUNK_SIZE_U64 + 1,
+    }
+}
+
+/// Length of chunk `index` of a message of `message_len` bytes cut into
+/// `chunks` chunks.
+fn chunk_len(message_len: u64, chunks: u64, index: u64) -> u64 {
+    if index + 1 == chunks {
+        message_len - index * CHUNK_SIZE_U64
+    } else {
+        CHUNK_SIZE_U64
+    }
+}
@@ -0,0 +1,116 @@
+//! Hash commitments with KangarooTwelve.
+//!
+//! A committer publishes the [`Commitment`] to a message returned by
+//! `commit`, with the `rand_core` feature, and keeps the message and the
+//! [`Opening`] secret until they choose to reveal both, at which point
+//! anyone can check them with [`verify`].
+//!
+//! The commitment to `M` with the 32-byte random opening `R` is
+//! `K12(M, "KangarooTwelve commitment" || R, 32)`. It is hiding because
+//! `R` is unknown until opened, and binding with the collision resistance
+//! of KangarooTwelve, so a commitment cannot be opened to two messages.
+//! A bare hash of the message is not a commitment, as low-entropy messages
+//! can be recovered from it by guessing.
+//!
+//! ```
+//! # #[cfg(feature = "rand_core")]
+//! # fn main() {
+//! # let mut rng = k12::K12Rng::from_seed_bytes(b"doctest");
+//! use k12::commitment;
+//!
+//! // `rng` is a cryptographically secure generator, e.g. `rand::rngs::OsRng`
+//! let (commitment, opening) = commitment::commit(b"heads", &mut rng);
+//! // ...publish the commitment, later reveal the message and opening
+//! assert!(commitment::verify(&commitment, b"heads", &opening));
+//! assert!(!commitment::verify(&commitment, b"tails", &opening));
+//! # }
+//! # #[cfg(not(feature = "rand_core"))]
+//! # fn main() {}
+//! ```
+
+use crate::{labels, KangarooTwelve};
+use core::fmt;
+use digest::Update;
+
+/// Size of commitments and openings.
+const SIZE: usize = 32;
+
+/// Commitment to a message, to be published.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Commitment([u8; SIZE]);
+
+impl Commitment {
+    /// Commitment with the serialization `bytes`.
+    pub fn from_bytes(bytes: [u8; SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    /// Serialization of the commitment.
+    pub fn as_bytes(&self) -> &[u8; SIZE] {
+        &self.0
+    }
+}
+
+/// Randomness opening a [`Commitment`], to be kept secret until the
+/// message is revealed.
+#[derive(Clone)]
+pub struct Opening([u8; SIZE]);
+
+impl Opening {
+    /// Opening with the serialization `bytes`, which must be uniformly
+    /// random for the commitment to hide the message.
+    pub fn from_bytes(bytes: [u8; SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    /// Serialization of the opening.
+    pub fn as_bytes(&self) -> &[u8; SIZE] {
+        &self.0
+    }
+
+    /// Compute the commitment to `message` with this opening.
+    pub fn commitment(&self, message: &[u8]) -> Commitment {
+        let mut bytes = [0u8; SIZE];
+        KangarooTwelve::new()
+            .chain(message)
+            .finalize_tree_with(&[labels::COMMITMENT, &self.0])
+            .squeeze(&mut bytes);
+        Commitment(bytes)
+    }
+}
+
+impl fmt::Debug for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Opening { ... }")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Opening {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.0);
+    }
+}
+
+/// The opening is zeroized on drop.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for Opening {}
+
+/// Commit to `message` with an opening drawn from `rng`.
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+pub fn commit<R>(message: &[u8], rng: &mut R) -> (Commitment, Opening)
+where
+    R: rand_core::CryptoRng + rand_core::RngCore + ?Sized,
+{
+    let mut opening = Opening([0u8; SIZE]);
+    rng.fill_bytes(&mut opening.0);
+    (opening.commitment(message), opening)
+}
+
+/// Check that `opening` opens `commitment` to `message`.
+#[must_use]
+pub fn verify(commitment: &Commitment, message: &[u8], opening: &Opening) -> bool {
+    opening.commitment(message) == *commitment
+}
@@ -0,0 +1,218 @@
+//! Assembly of a tree from chunks submitted concurrently and out of order.
+
+use crate::{leaf_chaining_value, KangarooTwelve, Reader, CHAINING_VALUE_SIZE, CHUNK_SIZE_U64};
+use core::fmt;
+use digest::{ExtendableOutput, Update};
+use std::{collections::BTreeMap, sync::Mutex, vec::Vec};
+
+/// Error submitting chunks to a [`ConcurrentHasher`] or finalizing it, or
+/// checking chunks against a [`ChunkTree`](crate::ChunkTree).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ChunkError {
+    /// The chunk index is past the end of the message.
+    OutOfRange {
+        /// Index of the chunk
+        index: u64,
+    },
+    /// The chunk does not have the length implied by its index and the
+    /// length of the message.
+    InvalidLength {
+        /// Index of the chunk
+        index: u64,
+    },
+    /// The chunk has already been submitted.
+    Duplicate {
+        /// Index of the chunk
+        index: u64,
+    },
+    /// The chunk was never submitted.
+    Missing {
+        /// Index of the chunk
+        index: u64,
+    },
+    /// The chunk does not match its chaining value or copy in the tree.
+    Mismatch {
+        /// Index of the chunk
+        index: u64,
+    },
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ChunkError::OutOfRange { index } => write!(f, "chunk {} is out of range", index),
+            ChunkError::InvalidLength { index } => {
+                write!(f, "chunk {} has an invalid length", index)
+            }
+            ChunkError::Duplicate { index } => write!(f, "chunk {} was submitted twice", index),
+            ChunkError::Missing { index } => write!(f, "chunk {} is missing", index),
+            ChunkError::Mismatch { index } => write!(f, "chunk {} does not match the tree", index),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+/// KangarooTwelve hasher for a message of known length whose chunks may be
+/// submitted concurrently from several threads, in any order.
+///
+/// The message is cut into chunks of [`CHUNK_SIZE`](crate::CHUNK_SIZE)
+/// bytes, the last of which may be shorter. Chunks other than the first and
+/// the last are hashed into their chaining value on the submitting thread.
+/// The first chunk and the chaining values following it without gaps are
+/// absorbed as soon as they arrive, so only the chaining values of chunks
+/// submitted ahead of a missing one are kept, 32 bytes each, along with a
+/// copy of the last chunk until finalization. Nothing is allocated up front,
+/// whatever the length of the message.
+///
+/// ```
+/// use k12::{digest::XofReader, ConcurrentHasher, CHUNK_SIZE};
+/// use std::{sync::Arc, thread};
+///
+/// let message = Arc::new(vec![0x42; 5 * CHUNK_SIZE + 123]);
+/// let hasher = Arc::new(ConcurrentHasher::new(message.len() as u64));
+/// let fetches: Vec<_> = (0..hasher.chunk_count())
+///     .map(|index| {
+///         let (message, hasher) = (Arc::clone(&message), Arc::clone(&hasher));
+///         thread::spawn(move || {
+///             let chunk = message.chunks(CHUNK_SIZE).nth(index as usize).unwrap();
+///             hasher.submit(index, chunk).unwrap();
+///         })
+///     })
+///     .collect();
+/// for fetch in fetches {
+///     fetch.join().unwrap();
+/// }
+///
+/// let hasher = Arc::try_unwrap(hasher).unwrap();
+/// let mut output = [0u8; 32];
+/// hasher.finalize_xof().unwrap().read(&mut output);
+/// ```
+#[derive(Debug)]
+pub struct ConcurrentHasher<'cs> {
+    message_len: u64,
+
+    /// Total number of chunks, at least 1 even for an empty message
+    chunks: u64,
+
+    state: Mutex<State<'cs>>,
+}
+
+#[derive(Debug)]
+struct State<'cs> {
+    /// Hasher of the chunks absorbed so far
+    hasher: KangarooTwelve<'cs>,
+
+    /// Index of the next chunk to absorb into `hasher`
+    next: u64,
+
+    /// Chaining values of chunks submitted before the ones preceding them
+    pending: BTreeMap<u64, [u8; CHAINING_VALUE_SIZE]>,
+
+    last: Option<Vec<u8>>,
+}
+
+impl<'cs> ConcurrentHasher<'cs> {
+    /// Create a hasher for a message of `message_len` bytes.
+    pub fn new(message_len: u64) -> Self {
+        Self::new_with_customization(message_len, &[])
+    }
+
+    /// Create a hasher for a message of `message_len` bytes with the given
+    /// customization.
+    pub fn new_with_customization(message_len: u64, customization: &'cs [u8]) -> Self {
+        let chunks = match message_len {
+            0 => 1,
+            len => (len - 1) / CHUNK_SIZE_U64 + 1,
+        };
+        Self {
+            message_len,
+            chunks,
+            state: Mutex::new(State {
+                hasher: KangarooTwelve::new_with_customization(customization),
+                next: 0,
+                pending: BTreeMap::new(),
+                last: None,
+            }),
+        }
+    }
+
+    /// Number of chunks the message is cut into.
+    pub fn chunk_count(&self) -> u64 {
+        self.chunks
+    }
+
+    /// Submit chunk `index` of the message.
+    pub fn submit(&self, index: u64, chunk: &[u8]) -> Result<(), ChunkError> {
+        if index >= self.chunks {
+            return Err(ChunkError::OutOfRange { index });
+        }
+        let expected_len = if index + 1 == self.chunks {
+            self.message_len - index * CHUNK_SIZE_U64
+        } else {
+            CHUNK_SIZE_U64
+        };
+        if chunk.len() as u64 != expected_len {
+            return Err(ChunkError::InvalidLength { index });
+        }
+
+        if index == 0 {
+            let mut state = self.state.lock().unwrap();
+            if state.next > 0 {
+                return Err(ChunkError::Duplicate { index });
+            }
+            state.hasher.update(chunk);
+            state.next = 1;
+            state.absorb_pending();
+        } else if index + 1 == self.chunks {
+            let mut state = self.state.lock().unwrap();
+            if state.last.is_some() {
+                return Err(ChunkError::Duplicate { index });
+            }
+            state.last = Some(chunk.to_vec());
+        } else {
+            // Hash outside of the lock
+            let cv = leaf_chaining_value(chunk);
+            let mut state = self.state.lock().unwrap();
+            if index < state.next || state.pending.contains_key(&index) {
+                return Err(ChunkError::Duplicate { index });
+            }
+            state.pending.insert(index, cv);
+            state.absorb_pending();
+        }
+        Ok(())
+    }
+
+    /// Finalize the hasher once every chunk has been submitted.
+    pub fn finalize_xof(self) -> Result<Reader, ChunkError> {
+        let state = self.state.into_inner().unwrap();
+        let mut hasher = state.hasher;
+        if self.chunks > 1 {
+            if state.next + 1 < self.chunks {
+                return Err(ChunkError::Missing { index: state.next });
+            }
+            let last = state.last.ok_or(ChunkError::Missing {
+                index: self.chunks - 1,
+            })?;
+            hasher.update(&last);
+        } else if state.next == 0 {
+            return Err(ChunkError::Missing { index: 0 });
+        }
+        Ok(hasher.finalize_xof())
+    }
+}
+
+impl State<'_> {
+    /// Absorb the chaining values following the chunks absorbed so far, if
+    /// the first chunk has been.
+    fn absorb_pending(&mut self) {
+        if self.next == 0 {
+            return;
+        }
+        while let Some(cv) = self.pending.remove(&self.next) {
+            self.hasher.absorb_chaining_values(&[cv]);
+            self.next += 1;
+        }
+    }
+}
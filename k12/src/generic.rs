@@ -0,0 +1,204 @@
+//! The tree of KangarooTwelve over any TurboSHAKE sponge, hashing leaves on
+//! the calling thread, and its instances KT256, MarsupilamiFourteen and
+//! KangarooTwelve with any number of rounds.
+
+use crate::{
+    tree::{self, Tree},
+    turbo_shake::{TurboShake, RATE},
+    CHAINING_VALUE_SIZE, NO_CUSTOMIZATION,
+};
+use core::mem;
+use digest::{ExtendableOutput, ExtendableOutputReset, HashMarker, Reset, Update, XofReader};
+
+/// The tree of [`KangarooTwelve`](crate::KangarooTwelve) with sponges of
+/// rate `R` bytes on Keccak-p[1600, `ROUNDS`] and chaining values of `CV`
+/// bytes.
+///
+/// Input is processed in a streaming fashion in constant memory, like with
+/// [`KangarooTwelve`](crate::KangarooTwelve), but leaves are always hashed
+/// one at a time on the calling thread.
+///
+/// Use the aliases [`Kt256`](crate::Kt256),
+/// [`MarsupilamiFourteen`](crate::MarsupilamiFourteen) and
+/// [`KangarooTwelveGeneric`](crate::KangarooTwelveGeneric) rather than
+/// other parameters, which are not standard functions. `R` must be 1 to
+/// 200 and `ROUNDS` at most 24, i.e. Keccak-f\[1600\], or the hasher fails
+/// to compile:
+///
+/// ```compile_fail
+/// k12::KangarooTwelveGeneric::<25>::new();
+/// ```
+#[derive(Clone, Debug)]
+pub struct TreeHasher<'cs, const R: usize, const CV: usize, const ROUNDS: usize> {
+    /// The tree absorbed so far
+    tree: Tree<R, CV, ROUNDS>,
+
+    /// Customization string to apply
+    customization: &'cs [u8],
+}
+
+impl<'cs, const R: usize, const CV: usize, const ROUNDS: usize> TreeHasher<'cs, R, CV, ROUNDS> {
+    /// Evaluates only for a rate of 1 to 200 bytes and at most 24 rounds, so
+    /// that other parameters fail to compile.
+    const VALID: () = [()][(R == 0 || R > 200 || ROUNDS > 24) as usize];
+
+    /// Create a new hasher.
+    pub const fn new() -> Self {
+        Self::new_with_customization(NO_CUSTOMIZATION)
+    }
+
+    /// Create a new hasher with the given customization.
+    ///
+    /// The customization string is borrowed and absorbed at finalization,
+    /// as with [`KangarooTwelve::new_with_customization`](crate::KangarooTwelve::new_with_customization).
+    pub const fn new_with_customization(customization: &'cs [u8]) -> Self {
+        let () = Self::VALID;
+        Self {
+            tree: Tree::new(),
+            customization,
+        }
+    }
+
+    /// Absorb `data` and return the hasher, for chaining.
+    pub fn chain_update(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.update(data.as_ref());
+        self
+    }
+
+    /// Number of message bytes absorbed so far.
+    pub fn message_len(&self) -> u64 {
+        self.tree.message_len()
+    }
+}
+
+impl<const R: usize, const CV: usize, const ROUNDS: usize> Default
+    for TreeHasher<'_, R, CV, ROUNDS>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const R: usize, const CV: usize, const ROUNDS: usize> HashMarker
+    for TreeHasher<'_, R, CV, ROUNDS>
+{
+}
+
+impl<const R: usize, const CV: usize, const ROUNDS: usize> Update
+    for TreeHasher<'_, R, CV, ROUNDS>
+{
+    fn update(&mut self, bytes: &[u8]) {
+        self.tree
+            .absorb(bytes, &tree::hash_leaves_serial::<R, CV, ROUNDS>);
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl<const R: usize, const CV: usize, const ROUNDS: usize> std::io::Write
+    for TreeHasher<'_, R, CV, ROUNDS>
+{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<const R: usize, const CV: usize, const ROUNDS: usize> ExtendableOutput
+    for TreeHasher<'_, R, CV, ROUNDS>
+{
+    type Reader = TreeReader<R, ROUNDS>;
+
+    fn finalize_xof(self) -> Self::Reader {
+        let state = self.tree.finalize(
+            self.customization,
+            &tree::hash_leaves_serial::<R, CV, ROUNDS>,
+        );
+        TreeReader { state }
+    }
+}
+
+impl<const R: usize, const CV: usize, const ROUNDS: usize> ExtendableOutputReset
+    for TreeHasher<'_, R, CV, ROUNDS>
+{
+    fn finalize_xof_reset(&mut self) -> Self::Reader {
+        let fresh = Self::new_with_customization(self.customization);
+        mem::replace(self, fresh).finalize_xof()
+    }
+}
+
+impl<const R: usize, const CV: usize, const ROUNDS: usize> Reset for TreeHasher<'_, R, CV, ROUNDS> {
+    /// Reset the hasher, keeping the current customization string.
+    fn reset(&mut self) {
+        *self = Self::new_with_customization(self.customization);
+    }
+}
+
+/// The sponge states are zeroized on drop. The customization string is
+/// borrowed and left to its owner.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl<const R: usize, const CV: usize, const ROUNDS: usize> zeroize::ZeroizeOnDrop
+    for TreeHasher<'_, R, CV, ROUNDS>
+{
+}
+
+/// Extensible output reader of [`TreeHasher`].
+///
+/// Consecutive calls to [`XofReader::read`] continue the same output
+/// stream, and cloning a reader forks the stream at the current position.
+#[derive(Clone, Debug)]
+pub struct TreeReader<const R: usize, const ROUNDS: usize> {
+    /// Sponge state at the current position
+    state: TurboShake<R, ROUNDS>,
+}
+
+impl<const R: usize, const ROUNDS: usize> XofReader for TreeReader<R, ROUNDS> {
+    fn read(&mut self, output: &mut [u8]) {
+        self.state.squeeze(output);
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl<const R: usize, const ROUNDS: usize> std::io::Read for TreeReader<R, ROUNDS> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.state.squeeze(buf);
+        Ok(buf.len())
+    }
+}
+
+/// The sponge state is zeroized on drop.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl<const R: usize, const ROUNDS: usize> zeroize::ZeroizeOnDrop for TreeReader<R, ROUNDS> {}
+
+/// The tree of [`KangarooTwelve`](crate::KangarooTwelve) on
+/// Keccak-p[1600, `ROUNDS`], for cryptanalysis.
+///
+/// **This is not a standard function.** Only `ROUNDS = 12` is
+/// KangarooTwelve, with the same output as
+/// [`KangarooTwelve`](crate::KangarooTwelve), and no other number of rounds
+/// has been analysed for use in practice. Reduced-round instances are
+/// insecure by design. `ROUNDS` can be at most 24, i.e. Keccak-f\[1600\],
+/// as checked at compile time.
+///
+/// ```
+/// use k12::{digest::{ExtendableOutput, Update, XofReader}, KangarooTwelveGeneric};
+///
+/// let mut hasher = KangarooTwelveGeneric::<6>::new();
+/// hasher.update(b"hello world");
+/// let mut output = [0u8; 32];
+/// hasher.finalize_xof().read(&mut output);
+/// ```
+pub type KangarooTwelveGeneric<'cs, const ROUNDS: usize> =
+    TreeHasher<'cs, RATE, CHAINING_VALUE_SIZE, ROUNDS>;
+
+/// Extensible output reader of [`KangarooTwelveGeneric`].
+pub type KangarooTwelveGenericReader<const ROUNDS: usize> = TreeReader<RATE, ROUNDS>;
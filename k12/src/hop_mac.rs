@@ -0,0 +1,151 @@
+//! HopMAC, the message authentication code built on KangarooTwelve.
+
+use crate::{
+    tree::{self, Tree},
+    turbo_shake::{RATE, ROUNDS},
+    KangarooTwelve, CHAINING_VALUE_SIZE, NO_CUSTOMIZATION,
+};
+use core::fmt;
+use digest::{
+    consts::U32, crypto_common::KeySizeUser, ExtendableOutputReset, FixedOutput, FixedOutputReset,
+    InvalidLength, Key, KeyInit, MacMarker, Output, OutputSizeUser, Reset, Update, XofReader,
+};
+
+/// Size of the inner digest, twice the 128-bit security strength.
+const INNER_SIZE: usize = 32;
+
+/// Minimum size of keys, the 128-bit security strength.
+const MIN_KEY_SIZE: usize = 16;
+
+/// The HopMAC message authentication code, with KangarooTwelve.
+///
+/// HopMAC is defined in the KangarooTwelve paper ("KangarooTwelve: fast
+/// hashing based on Keccak-p", ACNS 2018) as
+/// `HopMAC(K, M, C, ℓ) = K12(K, K12(M, C, 32), ℓ)`: the message and its
+/// customization string are hashed without the key, and the 32-byte digest
+/// is then the customization string of a second call that hashes the key.
+/// The tag is 32 bytes, and the message is hashed like with
+/// [`KangarooTwelve`], on several threads where enabled.
+///
+/// Keys of any length from 16 bytes are accepted by
+/// [`new_from_slice`](KeyInit::new_from_slice), which rejects shorter keys
+/// with [`InvalidLength`]. They should be secret and uniformly random.
+///
+/// ```
+/// use k12::{digest::Mac, HopMac};
+///
+/// let mut mac = HopMac::new_from_slice(b"a secret key of 32 bytes or more").unwrap();
+/// mac.update(b"hello world");
+/// let tag = mac.finalize().into_bytes();
+///
+/// let mut mac = HopMac::new_from_slice(b"a secret key of 32 bytes or more").unwrap();
+/// mac.update(b"hello world");
+/// assert!(mac.verify_slice(&tag).is_ok());
+/// ```
+#[derive(Clone)]
+pub struct HopMac<'cs> {
+    /// Inner hasher of the message
+    inner: KangarooTwelve<'cs>,
+
+    /// Outer tree with the key absorbed
+    outer: Tree<RATE, CHAINING_VALUE_SIZE, ROUNDS>,
+}
+
+impl<'cs> HopMac<'cs> {
+    /// Create a new [`HopMac`] instance with the given key and
+    /// customization string.
+    ///
+    /// # Panics
+    ///
+    /// If the key is shorter than 16 bytes.
+    pub fn new_with_customization(key: &[u8], customization: &'cs [u8]) -> Self {
+        assert!(
+            key.len() >= MIN_KEY_SIZE,
+            "HopMAC keys must be at least 16 bytes"
+        );
+        let mut outer = Tree::new();
+        outer.absorb(
+            key,
+            &tree::hash_leaves_serial::<RATE, CHAINING_VALUE_SIZE, ROUNDS>,
+        );
+        Self {
+            inner: KangarooTwelve::new_with_customization(customization),
+            outer,
+        }
+    }
+
+    /// Compute the tag of the message absorbed so far into `out`, leaving
+    /// the inner hasher ready for a new message.
+    fn finalize_tag(&mut self, out: &mut Output<Self>) {
+        let mut inner = [0u8; INNER_SIZE];
+        self.inner.finalize_xof_reset().read(&mut inner);
+
+        let mut tag = self.outer.clone().finalize(
+            &inner,
+            &tree::hash_leaves_serial::<RATE, CHAINING_VALUE_SIZE, ROUNDS>,
+        );
+        tag.squeeze(out);
+    }
+}
+
+impl KeySizeUser for HopMac<'_> {
+    /// Nominal key size; keys of any length from 16 bytes are accepted by
+    /// [`KeyInit::new_from_slice`].
+    type KeySize = U32;
+}
+
+impl KeyInit for HopMac<'_> {
+    fn new(key: &Key<Self>) -> Self {
+        Self::new_with_customization(key, NO_CUSTOMIZATION)
+    }
+
+    fn new_from_slice(key: &[u8]) -> Result<Self, InvalidLength> {
+        if key.len() < MIN_KEY_SIZE {
+            return Err(InvalidLength);
+        }
+        Ok(Self::new_with_customization(key, NO_CUSTOMIZATION))
+    }
+}
+
+impl Update for HopMac<'_> {
+    fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+}
+
+impl OutputSizeUser for HopMac<'_> {
+    type OutputSize = U32;
+}
+
+impl FixedOutput for HopMac<'_> {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.finalize_tag(out);
+    }
+}
+
+impl FixedOutputReset for HopMac<'_> {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        self.finalize_tag(out);
+    }
+}
+
+impl Reset for HopMac<'_> {
+    /// Reset the MAC, keeping the key and customization string.
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl MacMarker for HopMac<'_> {}
+
+impl fmt::Debug for HopMac<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HopMac { ... }")
+    }
+}
+
+/// The sponge states are zeroized on drop, including the one with the key
+/// absorbed.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for HopMac<'_> {}
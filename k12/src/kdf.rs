@@ -0,0 +1,28 @@
+//! Key derivation with KangarooTwelve.
+
+use crate::{labels, KangarooTwelve};
+use digest::Update;
+
+/// Derive `okm.len()` bytes of output keying material from the input keying
+/// material `ikm`, for the purpose described by `context`.
+///
+/// The output is `K12(ikm, "KangarooTwelve KDF" || context, okm.len())`.
+/// As the context is in the customization string, no choice of `ikm` and
+/// `context` collides with another, and keys derived for different
+/// contexts are independent. The context should name the application and
+/// the purpose of the key, e.g. `b"example.com 2026 session keys"`.
+///
+/// `ikm` must have enough entropy on its own: this is not a password hash.
+/// Output of any length can be derived, and shorter outputs are prefixes
+/// of longer ones for the same inputs.
+///
+/// ```
+/// let mut key = [0u8; 32];
+/// k12::kdf(b"example.com 2026 session keys", b"shared secret", &mut key);
+/// ```
+pub fn kdf(context: &[u8], ikm: &[u8], okm: &mut [u8]) {
+    KangarooTwelve::new()
+        .chain(ikm)
+        .finalize_tree_with(&[labels::KDF, context])
+        .squeeze(okm);
+}
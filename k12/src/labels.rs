@@ -0,0 +1,46 @@
+//! Labels prefixed to the customization string by the constructions built
+//! on KangarooTwelve.
+//!
+//! No label is a prefix of another, so a label followed by any suffix never
+//! equals another label followed by some other suffix, and the outputs of
+//! the constructions are separated from each other. They are not separated
+//! from KangarooTwelve used directly: a customization string starting with
+//! a label gives the output of the corresponding construction.
+
+/// [`kdf`](crate::kdf()), followed by the context.
+pub(crate) const KDF: &[u8] = b"KangarooTwelve KDF";
+
+/// [`KangarooTwelve::derive`](crate::KangarooTwelve::derive), followed by
+/// the framed label and the customization string.
+pub(crate) const DERIVE: &[u8] = b"KangarooTwelve derive";
+
+/// [`K12Rng`](crate::K12Rng), as the whole customization string.
+#[cfg_attr(not(feature = "rand_core"), allow(dead_code))]
+pub(crate) const RNG: &[u8] = b"KangarooTwelve RNG";
+
+/// [`K12Stream`](crate::K12Stream), followed by the nonce.
+pub(crate) const STREAM: &[u8] = b"KangarooTwelve stream";
+
+/// [`K12Tuple`](crate::K12Tuple), followed by the customization string.
+pub(crate) const TUPLE: &[u8] = b"KangarooTwelve tuple";
+
+/// Commitments, followed by the opening.
+pub(crate) const COMMITMENT: &[u8] = b"KangarooTwelve commitment";
+
+/// [`K12Salted`](crate::K12Salted), followed by the customization string.
+pub(crate) const SALTED: &[u8] = b"KangarooTwelve salted";
+
+#[cfg(test)]
+mod tests {
+    use super::{COMMITMENT, DERIVE, KDF, RNG, SALTED, STREAM, TUPLE};
+
+    #[test]
+    fn prefix_free() {
+        let labels = [KDF, DERIVE, RNG, STREAM, TUPLE, COMMITMENT, SALTED];
+        for (i, a) in labels.iter().enumerate() {
+            for (j, b) in labels.iter().enumerate() {
+                assert!(i == j || !b.starts_with(a), "{:?} {:?}", a, b);
+            }
+        }
+    }
+}
@@ -63,7 +63,21 @@ macro_rules! FOR5 {
     };
 }
 
-pub const RC: [u64; 12] = [
+/// Round constants of all 24 rounds of Keccak-f[1600]. Keccak-p[1600, n]
+/// uses the last `n` of them.
+pub const RC_F: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
     0x000000008000808b,
     0x800000000000008b,
     0x8000000000008089,
@@ -78,6 +92,25 @@ pub const RC: [u64; 12] = [
     0x8000000080008008,
 ];
 
+/// Round constants of Keccak-p[1600, 12], used by the backends fixed to
+/// 12 rounds.
+#[cfg(any(
+    test,
+    target_feature = "avx512f",
+    all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")),
+    all(target_arch = "aarch64", target_feature = "neon", feature = "neon"),
+    feature = "simd"
+))]
+pub const RC: [u64; 12] = {
+    let mut rc = [0; 12];
+    let mut i = 0;
+    while i < 12 {
+        rc[i] = RC_F[12 + i];
+        i += 1;
+    }
+    rc
+};
+
 // (0..24).map(|t| ((t+1)*(t+2)/2) % 64)
 pub const RHO: [u32; 24] = [
     1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
@@ -86,12 +119,67 @@ pub const PI: [usize; 24] = [
     10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
 ];
 
-pub fn keccak(lanes: &mut [u64; 25]) {
+/// Lanes kept complemented during [`keccak`], so that χ needs only one NOT
+/// per plane instead of five.
+const COMPLEMENTED: [usize; 6] = [1, 2, 8, 12, 17, 20];
+
+/// Whether states passed to [`keccak`] are kept with the bits of each lane
+/// interleaved, on 32-bit targets where 64-bit rotates take several
+/// instructions. WebAssembly has 64-bit instructions, so it is excluded.
+const INTERLEAVED: bool = cfg!(all(
+    target_pointer_width = "32",
+    not(target_arch = "wasm32")
+));
+
+/// Keccak-p[1600, `rounds`] on a state of lanes converted by [`to_state`].
+///
+/// # Panics
+///
+/// If `rounds` is greater than 24.
+pub fn keccak(state: &mut [u64; 25], rounds: usize) {
+    if INTERLEAVED {
+        keccak_interleaved(state, rounds);
+    } else {
+        keccak_complemented(state, rounds);
+    }
+}
+
+/// Convert a lane to its form in the state, on 32-bit targets by
+/// [`interleave`].
+#[inline]
+pub fn to_state(lane: u64) -> u64 {
+    if INTERLEAVED {
+        interleave(lane)
+    } else {
+        lane
+    }
+}
+
+/// Byte `i` of a lane in the state.
+#[inline]
+pub fn state_byte(word: u64, i: usize) -> u8 {
+    if INTERLEAVED {
+        // Four bits each from the even and odd halves, spread back apart
+        let spread = |mut x: u64| {
+            x = (x >> (4 * i)) & 0xF;
+            x = (x | x << 2) & 0x33;
+            (x | x << 1) & 0x55
+        };
+        (spread(word) | spread(word >> 32) << 1) as u8
+    } else {
+        (word >> (8 * i)) as u8
+    }
+}
+
+fn keccak_complemented(lanes: &mut [u64; 25], rounds: usize) {
     let mut c = [0u64; 5];
     let (mut x, mut y): (usize, usize);
 
-    #[allow(clippy::needless_range_loop)]
-    for round in 0..12 {
+    for &i in COMPLEMENTED.iter() {
+        lanes[i] = !lanes[i];
+    }
+
+    for &rc in RC_F[24 - rounds..].iter() {
         // θ
         FOR5!(x, 1, {
             c[x] = lanes[x] ^ lanes[x + 5] ^ lanes[x + 10] ^ lanes[x + 15] ^ lanes[x + 20];
@@ -117,17 +205,327 @@ pub fn keccak(lanes: &mut [u64; 25]) {
             }
         );
 
+        // χ, on the complemented lanes of the input and output masks. The
+        // lanes complemented after θ, ρ and π are in 0, 2, 3, 5, 7, 10, 12,
+        // 16, 18, 19, 20 and 23.
+        c.copy_from_slice(&lanes[0..5]);
+        lanes[0] = c[0] ^ (c[1] | c[2]);
+        lanes[1] = c[1] ^ (!c[2] | c[3]);
+        lanes[2] = c[2] ^ (c[3] & c[4]);
+        lanes[3] = c[3] ^ (c[4] | c[0]);
+        lanes[4] = c[4] ^ (c[0] & c[1]);
+
+        c.copy_from_slice(&lanes[5..10]);
+        lanes[5] = c[0] ^ (c[1] | c[2]);
+        lanes[6] = c[1] ^ (c[2] & c[3]);
+        lanes[7] = c[2] ^ (c[3] | !c[4]);
+        lanes[8] = c[3] ^ (c[4] | c[0]);
+        lanes[9] = c[4] ^ (c[0] & c[1]);
+
+        c.copy_from_slice(&lanes[10..15]);
+        let not3 = !c[3];
+        lanes[10] = c[0] ^ (c[1] | c[2]);
+        lanes[11] = c[1] ^ (c[2] & c[3]);
+        lanes[12] = c[2] ^ (not3 & c[4]);
+        lanes[13] = not3 ^ (c[4] | c[0]);
+        lanes[14] = c[4] ^ (c[0] & c[1]);
+
+        c.copy_from_slice(&lanes[15..20]);
+        let not3 = !c[3];
+        lanes[15] = c[0] ^ (c[1] & c[2]);
+        lanes[16] = c[1] ^ (c[2] | c[3]);
+        lanes[17] = c[2] ^ (not3 | c[4]);
+        lanes[18] = not3 ^ (c[4] & c[0]);
+        lanes[19] = c[4] ^ (c[0] | c[1]);
+
+        c.copy_from_slice(&lanes[20..25]);
+        let not1 = !c[1];
+        lanes[20] = c[0] ^ (not1 & c[2]);
+        lanes[21] = not1 ^ (c[2] | c[3]);
+        lanes[22] = c[2] ^ (c[3] & c[4]);
+        lanes[23] = c[3] ^ (c[4] | c[0]);
+        lanes[24] = c[4] ^ (c[0] & c[1]);
+
+        // ι
+        lanes[0] ^= rc;
+    }
+
+    for &i in COMPLEMENTED.iter() {
+        lanes[i] = !lanes[i];
+    }
+}
+
+/// [`RC_F`] with the even and odd bits of each constant split as by
+/// [`interleave`].
+const RC_INTERLEAVED: [[u32; 2]; 24] = {
+    let mut rc = [[0; 2]; 24];
+    let mut i = 0;
+    while i < 24 {
+        let word = interleave(RC_F[i]);
+        rc[i] = [word as u32, (word >> 32) as u32];
+        i += 1;
+    }
+    rc
+};
+
+/// Keccak-p[1600, `rounds`] on 32-bit words, on a state of lanes converted
+/// by [`interleave`]. A rotate of a lane is then a rotate of each of its words
+/// by half the offset, swapping them if it is odd.
+fn keccak_interleaved(state: &mut [u64; 25], rounds: usize) {
+    let mut s = [[0u32; 2]; 25];
+    for (s, &word) in s.iter_mut().zip(state.iter()) {
+        *s = [word as u32, (word >> 32) as u32];
+    }
+
+    let mut b = [[0u32; 2]; 25];
+    let mut c = [[0u32; 2]; 5];
+    let mut d = [[0u32; 2]; 5];
+    let (mut x, mut y): (usize, usize);
+
+    for &rc in RC_INTERLEAVED[24 - rounds..].iter() {
+        // θ
+        FOR5!(x, 1, {
+            c[x] = xor32(
+                xor32(xor32(s[x], s[x + 5]), xor32(s[x + 10], s[x + 15])),
+                s[x + 20],
+            );
+        });
+        FOR5!(x, 1, {
+            d[x] = xor32(c[(x + 4) % 5], rotl32(c[(x + 1) % 5], 1));
+        });
+
+        // θ applied while moving each lane to its place for ρ and π
+        b[0] = xor32(s[0], d[0]);
+        b[1] = rotl32(xor32(s[6], d[1]), 44);
+        b[2] = rotl32(xor32(s[12], d[2]), 43);
+        b[3] = rotl32(xor32(s[18], d[3]), 21);
+        b[4] = rotl32(xor32(s[24], d[4]), 14);
+        b[5] = rotl32(xor32(s[3], d[3]), 28);
+        b[6] = rotl32(xor32(s[9], d[4]), 20);
+        b[7] = rotl32(xor32(s[10], d[0]), 3);
+        b[8] = rotl32(xor32(s[16], d[1]), 45);
+        b[9] = rotl32(xor32(s[22], d[2]), 61);
+        b[10] = rotl32(xor32(s[1], d[1]), 1);
+        b[11] = rotl32(xor32(s[7], d[2]), 6);
+        b[12] = rotl32(xor32(s[13], d[3]), 25);
+        b[13] = rotl32(xor32(s[19], d[4]), 8);
+        b[14] = rotl32(xor32(s[20], d[0]), 18);
+        b[15] = rotl32(xor32(s[4], d[4]), 27);
+        b[16] = rotl32(xor32(s[5], d[0]), 36);
+        b[17] = rotl32(xor32(s[11], d[1]), 10);
+        b[18] = rotl32(xor32(s[17], d[2]), 15);
+        b[19] = rotl32(xor32(s[23], d[3]), 56);
+        b[20] = rotl32(xor32(s[2], d[2]), 62);
+        b[21] = rotl32(xor32(s[8], d[3]), 55);
+        b[22] = rotl32(xor32(s[14], d[4]), 39);
+        b[23] = rotl32(xor32(s[15], d[0]), 41);
+        b[24] = rotl32(xor32(s[21], d[1]), 2);
+
+        // χ
+        FOR5!(y, 5, {
+            FOR5!(x, 1, {
+                s[x + y] = xor32(b[x + y], andn32(b[(x + 1) % 5 + y], b[(x + 2) % 5 + y]));
+            });
+        });
+
+        // ι
+        s[0] = xor32(s[0], rc);
+    }
+
+    for (s, word) in s.iter().zip(state.iter_mut()) {
+        *word = u64::from(s[0]) | u64::from(s[1]) << 32;
+    }
+}
+
+/// Move the even bits of a lane to its low half and the odd bits to its
+/// high half.
+const fn interleave(lane: u64) -> u64 {
+    let low = unshuffle(lane as u32);
+    let high = unshuffle((lane >> 32) as u32);
+    (low & 0xFFFF | high << 16) as u64 | ((low >> 16 | high & 0xFFFF0000) as u64) << 32
+}
+
+/// The inverse of [`interleave`].
+#[cfg(test)]
+fn deinterleave(word: u64) -> u64 {
+    let (even, odd) = (word as u32, (word >> 32) as u32);
+    let low = shuffle(even & 0xFFFF | odd << 16);
+    let high = shuffle(even >> 16 | odd & 0xFFFF0000);
+    u64::from(low) | u64::from(high) << 32
+}
+
+/// Move the even bits of `x` to its low half and the odd bits to its high
+/// half.
+const fn unshuffle(mut x: u32) -> u32 {
+    let mut t;
+    t = (x ^ (x >> 1)) & 0x22222222;
+    x ^= t ^ (t << 1);
+    t = (x ^ (x >> 2)) & 0x0C0C0C0C;
+    x ^= t ^ (t << 2);
+    t = (x ^ (x >> 4)) & 0x00F000F0;
+    x ^= t ^ (t << 4);
+    t = (x ^ (x >> 8)) & 0x0000FF00;
+    x ^ t ^ (t << 8)
+}
+
+/// The inverse of [`unshuffle`].
+#[cfg(test)]
+fn shuffle(mut x: u32) -> u32 {
+    let mut t;
+    t = (x ^ (x >> 8)) & 0x0000FF00;
+    x ^= t ^ (t << 8);
+    t = (x ^ (x >> 4)) & 0x00F000F0;
+    x ^= t ^ (t << 4);
+    t = (x ^ (x >> 2)) & 0x0C0C0C0C;
+    x ^= t ^ (t << 2);
+    t = (x ^ (x >> 1)) & 0x22222222;
+    x ^ t ^ (t << 1)
+}
+
+#[inline(always)]
+fn xor32(a: [u32; 2], b: [u32; 2]) -> [u32; 2] {
+    [a[0] ^ b[0], a[1] ^ b[1]]
+}
+
+#[inline(always)]
+fn andn32(a: [u32; 2], b: [u32; 2]) -> [u32; 2] {
+    [!a[0] & b[0], !a[1] & b[1]]
+}
+
+#[inline(always)]
+fn rotl32(a: [u32; 2], n: u32) -> [u32; 2] {
+    if n & 1 == 0 {
+        [a[0].rotate_left(n / 2), a[1].rotate_left(n / 2)]
+    } else {
+        [a[1].rotate_left(n / 2 + 1), a[0].rotate_left(n / 2)]
+    }
+}
+
+/// Keccak-p[1600, 12] applied to `N` independent states at once.
+///
+/// Lane `i` of every state is stored together in `lanes[i]`, so each step
+/// operates on `N`-element arrays that the compiler can keep in SIMD
+/// registers.
+#[cfg(any(test, target_feature = "avx512f"))]
+pub fn keccak_x<const N: usize>(lanes: &mut [[u64; N]; 25]) {
+    let mut c = [[0u64; N]; 5];
+    let (mut x, mut y): (usize, usize);
+
+    for &rc in RC.iter() {
+        // θ
+        FOR5!(x, 1, {
+            c[x] = xor(
+                xor(
+                    xor(lanes[x], lanes[x + 5]),
+                    xor(lanes[x + 10], lanes[x + 15]),
+                ),
+                lanes[x + 20],
+            );
+        });
+
+        FOR5!(x, 1, {
+            let d = xor(c[(x + 4) % 5], rotl(c[(x + 1) % 5], 1));
+            FOR5!(y, 5, {
+                lanes[x + y] = xor(lanes[x + y], d);
+            });
+        });
+
+        // ρ and π
+        let mut a = lanes[1];
+        x = 0;
+        REPEAT24!(
+            {
+                c[0] = lanes[PI[x]];
+                lanes[PI[x]] = rotl(a, RHO[x]);
+            },
+            {
+                a = c[0];
+                x += 1;
+            }
+        );
+
         // χ
         FOR5!(y, 5, {
             FOR5!(x, 1, {
                 c[x] = lanes[x + y];
             });
             FOR5!(x, 1, {
-                lanes[x + y] = c[x] ^ ((!c[(x + 1) % 5]) & c[(x + 2) % 5]);
+                lanes[x + y] = xor(c[x], andn(c[(x + 1) % 5], c[(x + 2) % 5]));
             });
         });
 
         // ι
-        lanes[0] ^= RC[round];
+        lanes[0] = xor(lanes[0], [rc; N]);
+    }
+}
+
+#[cfg(any(test, target_feature = "avx512f"))]
+#[inline(always)]
+fn xor<const N: usize>(mut a: [u64; N], b: [u64; N]) -> [u64; N] {
+    for (a, b) in a.iter_mut().zip(b.iter()) {
+        *a ^= b;
+    }
+    a
+}
+
+#[cfg(any(test, target_feature = "avx512f"))]
+#[inline(always)]
+fn andn<const N: usize>(mut a: [u64; N], b: [u64; N]) -> [u64; N] {
+    for (a, b) in a.iter_mut().zip(b.iter()) {
+        *a = !*a & b;
+    }
+    a
+}
+
+#[cfg(any(test, target_feature = "avx512f"))]
+#[inline(always)]
+fn rotl<const N: usize>(mut a: [u64; N], n: u32) -> [u64; N] {
+    for a in a.iter_mut() {
+        *a = a.rotate_left(n);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deinterleave, interleave, keccak_complemented, keccak_interleaved};
+
+    #[test]
+    fn interleave_bits() {
+        let lane = 0x0123456789ABCDEF;
+        let word = interleave(lane);
+        for i in 0..64 {
+            assert_eq!(word >> (i / 2 + 32 * (i % 2)) & 1, lane >> i & 1);
+        }
+        assert_eq!(deinterleave(word), lane);
+    }
+
+    #[test]
+    fn interleaved_matches_complemented() {
+        let mut expected = [0u64; 25];
+        for (i, lane) in expected.iter_mut().enumerate() {
+            *lane = (i as u64 + 1).wrapping_mul(0x9E3779B97F4A7C15);
+        }
+        let mut state = [0u64; 25];
+        for (word, &lane) in state.iter_mut().zip(expected.iter()) {
+            *word = interleave(lane);
+        }
+        for &rounds in &[12, 14, 24] {
+            keccak_interleaved(&mut state, rounds);
+            keccak_complemented(&mut expected, rounds);
+            for (&word, &lane) in state.iter().zip(expected.iter()) {
+                assert_eq!(deinterleave(word), lane);
+            }
+        }
+    }
+
+    #[test]
+    fn keccak_f() {
+        // Keccak-f[1600] of the all-zero state
+        let mut state = [0u64; 25];
+        keccak_complemented(&mut state, 24);
+        assert_eq!(state[0], 0xf1258f7940e1dde7);
+        assert_eq!(state[1], 0x84d5ccf933c0478a);
+        assert_eq!(state[24], 0xeaf1ff7b5ceca249);
     }
 }
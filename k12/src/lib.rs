@@ -9,250 +9,824 @@
 // <https://github.com/dhardy/hash-bench/blob/master/src/k12.rs>
 
 #![no_std]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/RustCrypto/media/6ee8e381/logo.svg",
     html_favicon_url = "https://raw.githubusercontent.com/RustCrypto/media/6ee8e381/logo.svg",
-    html_root_url = "https://docs.rs/k12/0.2.1"
+    html_root_url = "https://docs.rs/k12/0.3.0"
+)]
+// Unsafe code is only compiled in for the opt-in SIMD backends, which call
+// intrinsics, and for `Reader::read_uninit`. Default builds forbid it.
+#![cfg_attr(
+    not(any(
+        all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")),
+        all(target_arch = "aarch64", target_feature = "neon", feature = "neon"),
+        feature = "uninit"
+    )),
+    forbid(unsafe_code)
+)]
+#![cfg_attr(
+    any(
+        all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")),
+        all(target_arch = "aarch64", target_feature = "neon", feature = "neon"),
+        feature = "uninit"
+    ),
+    deny(unsafe_code)
 )]
-#![forbid(unsafe_code)]
 #![warn(missing_docs, rust_2018_idioms)]
 
-// TODO(tarcieri): eliminate alloc requirement
-#[macro_use]
+#[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+
+#[cfg(feature = "std")]
+extern crate std;
 
 pub use digest;
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+pub use rand_core;
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+pub use zeroize;
 
 #[macro_use]
 mod lanes;
 
-// TODO(tarcieri): eliminate usage of `Vec`
-use alloc::vec::Vec;
-use core::{cmp::min, convert::TryInto, mem};
-use digest::{ExtendableOutput, ExtendableOutputReset, HashMarker, Reset, Update, XofReader};
+#[cfg(all(target_arch = "aarch64", target_feature = "neon", feature = "neon"))]
+mod aarch64;
+mod backend;
+#[cfg(feature = "std")]
+mod chunk_tree;
+pub mod commitment;
+#[cfg(feature = "std")]
+mod concurrent;
+mod executor;
+mod generic;
+mod hop_mac;
+mod kdf;
+mod kt256;
+mod labels;
+mod m14;
+mod mac;
+#[cfg(any(
+    test,
+    target_feature = "avx512f",
+    all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")),
+    all(target_arch = "aarch64", target_feature = "neon", feature = "neon"),
+    feature = "simd"
+))]
+mod multi_buffer;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod parallel_hash;
+#[cfg(feature = "threads")]
+mod pipeline;
+mod reader;
+#[cfg(feature = "rand_core")]
+mod rng;
+mod salted;
+#[cfg(feature = "simd")]
+mod simd;
+mod stream;
+#[cfg(all(feature = "threads", not(feature = "parallel")))]
+mod threads;
+mod tree;
+mod tuple;
+mod turbo_shake;
+#[cfg(all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")))]
+mod x86;
+
+pub use crate::backend::{Backend, UnsupportedBackend};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use crate::chunk_tree::{ChunkTree, ChunkVerifier};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use crate::concurrent::{ChunkError, ConcurrentHasher};
+pub use crate::executor::Executor;
+pub use crate::generic::{
+    KangarooTwelveGeneric, KangarooTwelveGenericReader, TreeHasher, TreeReader,
+};
+pub use crate::hop_mac::HopMac;
+pub use crate::kdf::kdf;
+pub use crate::kt256::{Kt256, Kt256Reader};
+pub use crate::m14::{MarsupilamiFourteen, MarsupilamiFourteenReader};
+pub use crate::mac::K12Mac;
+pub use crate::parallel_hash::{
+    ParallelHash128, ParallelHash128Reader, ParallelHash256, ParallelHash256Reader,
+};
+#[cfg(feature = "threads")]
+pub use crate::pipeline::Pipeline;
+pub use crate::reader::{BufferedReader, Bytes, Chunks, Reader, Take};
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+pub use crate::rng::K12Rng;
+pub use crate::salted::K12Salted;
+pub use crate::stream::K12Stream;
+pub use crate::tuple::K12Tuple;
+pub use crate::turbo_shake::{
+    InvalidDomainSeparation, TurboShake128, TurboShake128Reader, TurboShake256, TurboShake256Reader,
+};
+
+use crate::{
+    executor::ExecutorRef,
+    parallel_hash::{left_encode, ENCODE_SIZE},
+    tree::Tree,
+    turbo_shake::{Sponge128, RATE, ROUNDS},
+};
+use core::{iter::FromIterator, mem};
+use digest::{ExtendableOutput, ExtendableOutputReset, HashMarker, Reset, Update};
+
+/// Size of the chunks `S_i` the input string is cut into.
+pub const CHUNK_SIZE: usize = 8192;
+
+/// [`CHUNK_SIZE`] as the type used for counting absorbed bytes.
+const CHUNK_SIZE_U64: u64 = CHUNK_SIZE as u64;
+
+/// Size of the chaining values `CV_i` computed for each leaf.
+const CHAINING_VALUE_SIZE: usize = 32;
+
+/// Bytes appended to `S_0` in the final node when there is more than one chunk.
+const FINAL_NODE_PRE: [u8; 8] = [3, 0, 0, 0, 0, 0, 0, 0];
+
+/// Bytes terminating the final node when there is more than one chunk.
+const FINAL_NODE_SUF: [u8; 2] = [0xFF, 0xFF];
+
+/// Domain separation byte used when the tree consists of a single node.
+const SINGLE_NODE_DS: u8 = 0x07;
+
+/// Domain separation byte used for the leaves.
+const LEAF_DS: u8 = 0x0B;
+
+/// Domain separation byte used for the final node of a multi-chunk tree.
+const FINAL_NODE_DS: u8 = 0x06;
+
+/// Empty customization string.
+const NO_CUSTOMIZATION: &[u8] = &[];
+
+/// Maximum length of the `right_encode` of a 64-bit integer.
+const RIGHT_ENCODE_SIZE: usize = 9;
+
+/// Most leaves hashed in lockstep by interleaving their states.
+///
+/// With AVX-512 enabled at compile time the compiler-vectorized interleaved
+/// permutation is used for 8 leaves. Otherwise on x86, the `avx2` feature
+/// hashes 4 leaves with AVX2 or 2 leaves with SSE2, and the `avx512`
+/// feature 8 leaves with AVX-512, if the CPU supports them. On aarch64, the
+/// `neon` feature hashes 2 leaves with NEON, or with the SHA3 instructions
+/// if `arm-sha3` is also enabled and the CPU supports them. Elsewhere, the
+/// `simd` feature hashes 2 leaves with `core::simd`.
+#[cfg(any(
+    target_feature = "avx512f",
+    all(feature = "avx512", any(target_arch = "x86", target_arch = "x86_64"))
+))]
+const LEAF_DEGREE: usize = 8;
+#[cfg(all(
+    feature = "avx2",
+    any(target_arch = "x86", target_arch = "x86_64"),
+    not(any(target_feature = "avx512f", feature = "avx512"))
+))]
+const LEAF_DEGREE: usize = 4;
+#[cfg(all(
+    not(any(
+        all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")),
+        target_feature = "avx512f"
+    )),
+    any(
+        all(target_arch = "aarch64", target_feature = "neon", feature = "neon"),
+        feature = "simd"
+    )
+))]
+const LEAF_DEGREE: usize = 2;
+#[cfg(not(any(
+    all(feature = "avx2", any(target_arch = "x86", target_arch = "x86_64")),
+    target_feature = "avx512f",
+    all(target_arch = "aarch64", target_feature = "neon", feature = "neon"),
+    feature = "simd"
+)))]
+const LEAF_DEGREE: usize = 1;
+
+/// Maximum number of whole leaves hashed together on the calling thread,
+/// bounding the stack space used for their chaining values.
+const LEAF_BATCH: usize = 64;
 
 /// The KangarooTwelve extendable-output function (XOF).
-#[derive(Debug, Default)]
-pub struct KangarooTwelve {
-    /// Input to be processed
-    // TODO(tarcieri): don't store input in a `Vec`
-    buffer: Vec<u8>,
+///
+/// Input is processed in a streaming fashion: `S_0` is absorbed directly
+/// into the final node, and each subsequent chunk is hashed into its
+/// chaining value as soon as it is complete.
+///
+/// # Memory usage
+///
+/// Hashing uses a constant amount of working memory regardless of the
+/// length of the message: the state consists of the sponge absorbing the
+/// final node, the sponge absorbing the current leaf and a byte counter,
+/// well under 16 KiB in total. [`Update::update`] never allocates, so
+/// no buffering limit is needed to bound the memory used when hashing
+/// untrusted input.
+///
+/// With the `parallel` feature, whole chunks passed to a single update are
+/// hashed on the rayon thread pool, which may allocate when scheduling
+/// them. The `threads` feature instead spreads them over scoped standard
+/// library threads, up to one per available CPU, without depending on
+/// rayon; it requires Rust 1.63 and is ignored when `parallel` is also
+/// enabled. Either way all the whole chunks of an update are distributed
+/// at once, their chaining values being collected in a heap buffer of 32
+/// bytes per chunk, i.e. 1/256 of the update. The input itself is never
+/// copied or buffered, and updates with too few whole chunks to be worth
+/// distributing are hashed on the calling thread.
+///
+/// In the browser, `parallel` hashes on web workers once the application
+/// has built wasm32 with the `atomics` target feature and initialized the
+/// rayon thread pool, e.g. with [`wasm-bindgen-rayon`]'s `initThreadPool`.
+/// No separate feature is needed, as only the application depends on the
+/// glue crate. Without threads, rayon 1.6 or newer runs the leaves on the
+/// calling thread instead.
+///
+/// [`wasm-bindgen-rayon`]: https://docs.rs/wasm-bindgen-rayon
+///
+/// The customization string is borrowed for the lifetime `'cs`, so no
+/// allocator is required. The constructors are `const fn`, which allows
+/// a hasher with a fixed, compile-time known footprint to be placed in a
+/// `const` or `static` on embedded targets.
+///
+/// # Shared prefixes
+///
+/// Cloning a hasher snapshots the absorbed state, so messages sharing a
+/// common prefix can be hashed without reprocessing it:
+///
+/// ```
+/// use k12::{digest::{ExtendableOutput, Update, XofReader}, KangarooTwelve};
+///
+/// let mut prefix = KangarooTwelve::new();
+/// prefix.update(b"protocol header");
+///
+/// for msg in &["first message", "second message"] {
+///     let mut h = prefix.clone();
+///     h.update(msg.as_bytes());
+///     let mut output = [0u8; 32];
+///     h.finalize_xof().read(&mut output);
+/// }
+/// ```
+///
+/// # Async code
+///
+/// A hasher without a customization string, or with a `'static` one, is
+/// `Send + 'static`, so a large owned buffer can be hashed on the blocking
+/// thread pool of any async runtime without extra support from this crate,
+/// e.g. with tokio:
+///
+/// ```ignore
+/// let digest = tokio::task::spawn_blocking(move || {
+///     KangarooTwelve::new().chain_update(&buffer).finalize_boxed(32)
+/// })
+/// .await?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KangarooTwelve<'cs> {
+    /// The tree absorbed so far
+    tree: Tree<RATE, CHAINING_VALUE_SIZE, ROUNDS>,
 
     /// Customization string to apply
-    // TODO(tarcieri): don't store customization in a `Vec`
-    customization: Vec<u8>,
+    customization: &'cs [u8],
+
+    /// Executor hashing leaves instead of the built-in backends
+    executor: ExecutorRef<'cs>,
+
+    /// Backend hashing leaves, or `None` for the fastest one
+    backend: Option<Backend>,
+
+    /// Maximum number of threads hashing leaves, or 0 for no limit
+    #[cfg(any(feature = "parallel", feature = "threads"))]
+    max_threads: usize,
 }
 
-impl KangarooTwelve {
+impl<'cs> KangarooTwelve<'cs> {
     /// Create a new [`KangarooTwelve`] instance.
-    pub fn new() -> Self {
-        Self::default()
+    pub const fn new() -> Self {
+        Self::new_with_customization(NO_CUSTOMIZATION)
     }
 
     /// Create a new [`KangarooTwelve`] instance with the given customization.
-    pub fn new_with_customization(customization: impl AsRef<[u8]>) -> Self {
+    ///
+    /// KangarooTwelve appends the customization string to the message
+    /// (`S = M || C || right_encode(|C|)`), so it cannot be absorbed until
+    /// finalization. It is borrowed rather than copied, so its length has
+    /// no impact on the memory used by the hasher.
+    pub const fn new_with_customization(customization: &'cs [u8]) -> Self {
         Self {
-            buffer: Vec::new(),
-            customization: customization.as_ref().into(),
+            tree: Tree::new(),
+            customization,
+            executor: ExecutorRef::NONE,
+            backend: None,
+            #[cfg(any(feature = "parallel", feature = "threads"))]
+            max_threads: 0,
         }
     }
-}
 
-impl HashMarker for KangarooTwelve {}
+    /// Hash the leaves of large updates with `executor` and return the
+    /// hasher.
+    ///
+    /// This takes precedence over the `parallel` and `threads` features, and
+    /// over the limit set by `with_threads`. The executor is kept by clones
+    /// and resets.
+    pub fn with_executor(mut self, executor: &'cs dyn Executor) -> Self {
+        self.executor = ExecutorRef(Some(executor));
+        self
+    }
 
-impl Update for KangarooTwelve {
-    fn update(&mut self, bytes: &[u8]) {
-        self.buffer.extend_from_slice(bytes);
+    /// Hash leaves with `backend` instead of the fastest one available, and
+    /// return the hasher.
+    ///
+    /// This applies to leaves hashed on other threads or by an executor as
+    /// well. Leaves absorbed in pieces smaller than a chunk, and those of a
+    /// `Pipeline`, are always hashed one at a time. The backend is kept by
+    /// clones and resets.
+    ///
+    /// # Errors
+    ///
+    /// If `backend` is not compiled in or not supported by the CPU.
+    pub fn with_backend(mut self, backend: Backend) -> Result<Self, UnsupportedBackend> {
+        if !backend.is_available() {
+            return Err(UnsupportedBackend(backend));
+        }
+        self.backend = Some(backend);
+        Ok(self)
     }
-}
 
-impl ExtendableOutput for KangarooTwelve {
-    type Reader = Reader;
+    /// Hash the leaves of the rest of the message on `workers` background
+    /// threads, or one per available CPU if `workers` is 0, while the
+    /// caller keeps absorbing input.
+    #[cfg(feature = "threads")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "threads")))]
+    pub fn pipelined(self, workers: usize) -> Pipeline<'cs> {
+        Pipeline::new(self, workers)
+    }
 
-    fn finalize_xof(self) -> Self::Reader {
-        Reader {
-            buffer: self.buffer,
-            customization: self.customization,
-            finished: false,
-        }
+    /// Limit the number of threads hashing the leaves of large updates to
+    /// `n`, including the calling thread, and return the hasher.
+    ///
+    /// With `n == 1` all hashing happens on the calling thread. The default
+    /// of 0 sets no limit beyond the size of the rayon thread pool or, with
+    /// the `threads` feature, the number of available CPUs. With `threads`,
+    /// `n` threads are used for large enough updates even if there are fewer
+    /// CPUs. The limit is kept by clones and resets.
+    #[cfg(any(feature = "parallel", feature = "threads"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "parallel", feature = "threads"))))]
+    pub fn with_threads(mut self, n: usize) -> Self {
+        self.max_threads = n;
+        self
     }
-}
 
-impl ExtendableOutputReset for KangarooTwelve {
-    fn finalize_xof_reset(&mut self) -> Self::Reader {
-        let mut buffer = vec![];
-        let mut customization = vec![];
+    /// Absorb `data` and return the hasher, for chaining.
+    pub fn chain_update(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.absorb(data.as_ref());
+        self
+    }
 
-        mem::swap(&mut self.buffer, &mut buffer);
-        mem::swap(&mut self.customization, &mut customization);
+    /// Set the customization string and return the hasher, for chaining.
+    ///
+    /// The customization string is only absorbed at finalization, so it may
+    /// be set at any point before then.
+    pub fn chain_customization(mut self, customization: &'cs [u8]) -> Self {
+        self.customization = customization;
+        self
+    }
 
-        Reader {
-            buffer,
-            customization,
-            finished: false,
-        }
+    /// Reset the hasher to its initial state, keeping the current
+    /// customization string.
+    ///
+    /// This is the behavior of both [`Reset::reset`] and
+    /// [`ExtendableOutputReset::finalize_xof_reset`].
+    pub fn reset_keeping_customization(&mut self) {
+        *self = self.fresh(self.customization);
     }
-}
 
-impl Reset for KangarooTwelve {
-    fn reset(&mut self) {
-        self.buffer.clear();
+    /// Reset the hasher to its initial state and replace the customization
+    /// string with `customization`.
+    pub fn reset_with_customization(&mut self, customization: &'cs [u8]) {
+        *self = self.fresh(customization);
     }
-}
 
-/// Extensible output reader.
-///
-/// NOTE: this presently only supports one invocation and will *panic* if
-/// [`XofReader::read`] is invoked on it multiple times.
-#[derive(Debug, Default)]
-pub struct Reader {
-    /// Input to be processed
-    // TODO(tarcieri): don't store input in a `Vec`
-    buffer: Vec<u8>,
+    /// Finalize the message absorbed so far without consuming the hasher,
+    /// which can continue absorbing input afterwards.
+    ///
+    /// This only copies the fixed-size hasher state, regardless of how much
+    /// input has been absorbed.
+    pub fn finalize_xof_ref(&self) -> Reader {
+        self.clone().finalize_xof()
+    }
 
-    /// Customization string to apply
-    // TODO(tarcieri): don't store customization in a `Vec`
-    customization: Vec<u8>,
+    /// Finalize the hasher and return the first `N` bytes of output.
+    pub fn finalize_xof_array<const N: usize>(self) -> [u8; N] {
+        self.finalize_xof().read_array()
+    }
 
-    /// Has the XOF output already been consumed?
-    // TODO(tarcieri): allow `XofReader::result` to be called multiple times
-    finished: bool,
-}
+    /// Finalize the hasher into a reader limited to `max_len` bytes of
+    /// output.
+    ///
+    /// [`Take::try_read`] fails on reads past the limit, for protocols whose
+    /// security depends on the output length.
+    pub fn finalize_xof_limited(self, max_len: u64) -> Take {
+        self.finalize_xof().limit(max_len)
+    }
 
-// TODO(tarcieri): factor more of this logic into the `KangarooTwelve` type
-impl XofReader for Reader {
-    /// Get the resulting output of the function.
+    /// Derive `output.len()` bytes for the purpose named `label` from the
+    /// message absorbed so far, without consuming the hasher.
     ///
-    /// Panics if called multiple times on the same instance (TODO: don't panic!)
-    fn read(&mut self, output: &mut [u8]) {
-        assert!(
-            !self.finished,
-            "not yet implemented: multiple XofReader::read invocations unsupported"
-        );
+    /// Outputs derived with different labels are independent of each other
+    /// and of the output stream of the hasher, so one hash of a secret can
+    /// yield e.g. an encryption key, a MAC key and an IV, while its digest
+    /// is published. The derived output is
+    /// `K12(S, "KangarooTwelve derive" || left_encode(|label|) || label || C)`
+    /// with the message `S`, the customization string `C` and the length of
+    /// the label in bits encoded as in NIST SP 800-185. Shorter outputs are
+    /// prefixes of longer ones for the same label.
+    ///
+    /// ```
+    /// use k12::{digest::Update, KangarooTwelve};
+    ///
+    /// let hasher = KangarooTwelve::new().chain(b"shared secret");
+    /// let (mut enc_key, mut mac_key, mut iv) = ([0u8; 32], [0u8; 32], [0u8; 16]);
+    /// hasher.derive(b"encryption key", &mut enc_key);
+    /// hasher.derive(b"mac key", &mut mac_key);
+    /// hasher.derive(b"iv", &mut iv);
+    /// ```
+    pub fn derive(&self, label: &[u8], output: &mut [u8]) {
+        let mut buffer = [0u8; ENCODE_SIZE];
+        let label_len = left_encode(label.len() as u64 * 8, &mut buffer);
+        self.clone()
+            .finalize_tree_with(&[labels::DERIVE, label_len, label, self.customization])
+            .squeeze(output);
+    }
 
-        let b = 8192;
-        let c = 256;
+    /// Number of message bytes absorbed so far.
+    pub fn message_len(&self) -> u64 {
+        self.tree.message_len()
+    }
+
+    /// Number of leaves which have been completed and hashed into chaining
+    /// values so far.
+    pub fn chunks_processed(&self) -> u64 {
+        self.message_len().saturating_sub(CHUNK_SIZE_U64) / CHUNK_SIZE_U64
+    }
 
-        let mut slice = Vec::new(); // S
-        slice.extend_from_slice(&self.buffer);
-        slice.extend_from_slice(&self.customization);
-        slice.extend_from_slice(&right_encode(self.customization.len())[..]);
+    /// Number of bytes absorbed into the current, incomplete chunk.
+    ///
+    /// These bytes have already been absorbed into a sponge, but the chunk
+    /// they belong to has not been completed yet.
+    pub fn buffered_len(&self) -> usize {
+        self.tree.buffered_len()
+    }
 
-        // === Cut the input string into chunks of b bytes ===
-        let n = (slice.len() + b - 1) / b;
-        let mut slices = Vec::with_capacity(n); // Si
-        for i in 0..n {
-            let ub = min((i + 1) * b, slice.len());
-            slices.push(&slice[i * b..ub]);
+    /// Absorb the chaining values of whole chunks hashed elsewhere with
+    /// [`chaining_values`], as if the chunks themselves had been absorbed.
+    ///
+    /// This allows the leaves of a large message to be hashed on other
+    /// threads or machines: a coordinator absorbs the first chunk with
+    /// [`Update::update`], then the chaining values of the following whole
+    /// chunks in order, then the rest of the message as usual.
+    ///
+    /// ```
+    /// use k12::{digest::{ExtendableOutput, Update}, chaining_values, KangarooTwelve, CHUNK_SIZE};
+    ///
+    /// let message = vec![0x42; 10 * CHUNK_SIZE + 123];
+    /// let (first, rest) = message.split_at(CHUNK_SIZE);
+    /// let (middle, tail) = rest.split_at(9 * CHUNK_SIZE);
+    ///
+    /// // On the workers
+    /// let mut cvs = [[0u8; 32]; 9];
+    /// for (range, cvs) in middle.chunks(3 * CHUNK_SIZE).zip(cvs.chunks_mut(3)) {
+    ///     chaining_values(range, cvs);
+    /// }
+    ///
+    /// // On the coordinator
+    /// let mut h = KangarooTwelve::new();
+    /// h.update(first);
+    /// h.absorb_chaining_values(&cvs);
+    /// h.update(tail);
+    /// assert_eq!(h.finalize_boxed(32), KangarooTwelve::new().chain(&message).finalize_boxed(32));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the number of bytes absorbed so far is not a positive multiple of
+    /// [`CHUNK_SIZE`].
+    pub fn absorb_chaining_values(&mut self, cvs: &[[u8; CHAINING_VALUE_SIZE]]) {
+        self.tree.absorb_chaining_values(cvs);
+    }
+
+    /// Absorb each of the given slices in order, as if they had been
+    /// concatenated.
+    pub fn update_slices(&mut self, slices: &[&[u8]]) {
+        self.update_iter(slices);
+    }
+
+    /// Absorb each of the byte slices yielded by `iter` in order, as if
+    /// they had been concatenated.
+    pub fn update_iter<I>(&mut self, iter: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        for bytes in iter {
+            self.absorb(bytes.as_ref());
         }
+    }
 
-        // TODO(tarcieri): get rid of intermediate output buffer
-        let tmp_buffer = if n == 1 {
-            // === Process the tree with only a final node ===
-            f(slices[0], 0x07, output.len())
-        } else {
-            // === Process the tree with kangaroo hopping ===
-            // TODO: in parallel
-            let mut intermediate = Vec::with_capacity(n - 1); // CVi
-            for i in 0..n - 1 {
-                intermediate.push(f(slices[i + 1], 0x0B, c / 8));
-            }
+    /// Absorb scatter/gather buffers in order, as if they had been
+    /// concatenated.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn update_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) {
+        for buf in bufs {
+            self.absorb(buf);
+        }
+    }
 
-            let mut node_star = Vec::new();
-            node_star.extend_from_slice(slices[0]);
-            node_star.extend_from_slice(&[3, 0, 0, 0, 0, 0, 0, 0]);
+    /// Create a hasher in its initial state, with the same settings as this
+    /// one apart from the customization string.
+    fn fresh(&self, customization: &'cs [u8]) -> Self {
+        Self {
+            executor: self.executor,
+            backend: self.backend,
+            #[cfg(any(feature = "parallel", feature = "threads"))]
+            max_threads: self.max_threads,
+            ..Self::new_with_customization(customization)
+        }
+    }
 
-            #[allow(clippy::needless_range_loop)]
-            for i in 0..n - 1 {
-                node_star.extend_from_slice(&intermediate[i][..]);
+    /// Number of bytes to absorb before reaching the start of a leaf, or 0
+    /// if the hasher is at one.
+    #[cfg(feature = "threads")]
+    pub(crate) fn leaf_boundary_distance(&self) -> usize {
+        self.tree.leaf_boundary_distance()
+    }
+
+    /// Absorb bytes of the input string `S` into the tree.
+    fn absorb(&mut self, bytes: &[u8]) {
+        let hash_leaves = self.leaf_hasher();
+        self.tree
+            .absorb_batched(bytes, &hash_leaves, self.max_leaves_per_batch());
+    }
+
+    /// Most whole chunks handed to the leaf hasher at once: all those of an
+    /// update when they may be spread over threads, so that each thread
+    /// gets its share in one go, and otherwise [`LEAF_BATCH`].
+    fn max_leaves_per_batch(&self) -> usize {
+        #[cfg(feature = "alloc")]
+        {
+            #[cfg(any(feature = "parallel", feature = "threads"))]
+            let threads = self.max_threads != 1;
+            #[cfg(not(any(feature = "parallel", feature = "threads")))]
+            let threads = false;
+            if threads || self.executor.0.is_some() {
+                return usize::MAX;
             }
+        }
+        LEAF_BATCH
+    }
 
-            node_star.extend_from_slice(&right_encode(n - 1));
-            node_star.extend_from_slice(b"\xFF\xFF");
+    /// Hashing of batches of whole chunks as leaves, with the executor,
+    /// threads and backend set on this hasher.
+    fn leaf_hasher(&self) -> impl Fn(&[u8], &mut [[u8; CHAINING_VALUE_SIZE]]) + 'cs {
+        let executor = self.executor;
+        let backend = self.backend.unwrap_or_else(Backend::detect);
+        #[cfg(any(feature = "parallel", feature = "threads"))]
+        let max_threads = self.max_threads;
+
+        move |chunks, cvs| {
+            if let ExecutorRef(Some(executor)) = executor {
+                executor::hash_leaves(executor, backend, chunks, cvs);
+            } else {
+                #[cfg(feature = "parallel")]
+                parallel::hash_leaves(backend, chunks, cvs, max_threads);
+                #[cfg(all(feature = "threads", not(feature = "parallel")))]
+                threads::hash_leaves(backend, chunks, cvs, max_threads);
+                #[cfg(not(any(feature = "parallel", feature = "threads")))]
+                backend.hash_leaves(chunks, cvs);
+            }
+        }
+    }
 
-            f(&node_star[..], 0x06, output.len())
-        };
+    /// Absorb the customization string and finish the tree, returning the
+    /// finalized sponge output is squeezed from.
+    fn finalize_tree(self) -> Sponge128 {
+        let customization = self.customization;
+        self.finalize_tree_with(&[customization])
+    }
+
+    /// Finish the tree like [`finalize_tree`](Self::finalize_tree), in
+    /// place of the customization string with the concatenation of `parts`.
+    pub(crate) fn finalize_tree_with(self, parts: &[&[u8]]) -> Sponge128 {
+        let hash_leaves = self.leaf_hasher();
+        self.tree.finalize_parts(parts, &hash_leaves)
+    }
+}
+
+impl HashMarker for KangarooTwelve<'_> {}
+
+impl Update for KangarooTwelve<'_> {
+    /// Absorb `bytes` into the hasher.
+    ///
+    /// Input is hashed directly from `bytes` and never copied, so large
+    /// buffers that are already resident in memory (e.g. memory-mapped
+    /// files) can be passed as-is.
+    fn update(&mut self, bytes: &[u8]) {
+        self.absorb(bytes);
+    }
+}
+
+/// Allows hashing arbitrarily large [`std::io::Read`] sources in constant
+/// memory with [`std::io::copy`].
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::io::Write for KangarooTwelve<'_> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.absorb(buf);
+        Ok(buf.len())
+    }
 
-        output.copy_from_slice(&tmp_buffer);
-        self.finished = true;
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
 }
 
-fn f(input: &[u8], suffix: u8, mut output_len: usize) -> Vec<u8> {
-    let mut state = [0u8; 200];
-    let max_block_size = 1344 / 8; // r, also known as rate in bytes
-
-    // === Absorb all the input blocks ===
-    // We unroll first loop, which allows simple copy
-    let mut block_size = min(input.len(), max_block_size);
-    state[0..block_size].copy_from_slice(&input[0..block_size]);
-
-    let mut offset = block_size;
-    while offset < input.len() {
-        keccak(&mut state);
-        block_size = min(input.len() - offset, max_block_size);
-        for i in 0..block_size {
-            // TODO: is this sufficiently optimisable or better to convert to u64 first?
-            state[i] ^= input[i + offset];
+impl Extend<u8> for KangarooTwelve<'_> {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) {
+        // Collect bytes into blocks so they aren't absorbed one at a time
+        let mut block = [0u8; RATE];
+        let mut len = 0;
+        for byte in iter {
+            block[len] = byte;
+            len += 1;
+            if len == block.len() {
+                self.absorb(&block);
+                len = 0;
+            }
         }
-        offset += block_size;
-    }
-    if block_size == max_block_size {
-        // TODO: condition is nearly always false; tests pass without this.
-        // Why is it here?
-        keccak(&mut state);
-        block_size = 0;
-    }
-
-    // === Do the padding and switch to the squeezing phase ===
-    state[block_size] ^= suffix;
-    if ((suffix & 0x80) != 0) && (block_size == (max_block_size - 1)) {
-        // TODO: condition is almost always false — in fact tests pass without
-        // this block! So why is it here?
-        keccak(&mut state);
-    }
-    state[max_block_size - 1] ^= 0x80;
-    keccak(&mut state);
-
-    // === Squeeze out all the output blocks ===
-    let mut output = Vec::with_capacity(output_len);
-    while output_len > 0 {
-        block_size = min(output_len, max_block_size);
-        output.extend_from_slice(&state[0..block_size]);
-        output_len -= block_size;
-        if output_len > 0 {
-            keccak(&mut state);
+        self.absorb(&block[..len]);
+    }
+}
+
+impl<'a> Extend<&'a u8> for KangarooTwelve<'_> {
+    fn extend<I: IntoIterator<Item = &'a u8>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl FromIterator<u8> for KangarooTwelve<'_> {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+        let mut hasher = Self::new();
+        hasher.extend(iter);
+        hasher
+    }
+}
+
+impl ExtendableOutput for KangarooTwelve<'_> {
+    type Reader = Reader;
+
+    fn finalize_xof(self) -> Self::Reader {
+        Reader::new(self.finalize_tree())
+    }
+}
+
+impl ExtendableOutputReset for KangarooTwelve<'_> {
+    fn finalize_xof_reset(&mut self) -> Self::Reader {
+        let customization = self.customization;
+        let fresh = self.fresh(customization);
+        mem::replace(self, fresh).finalize_xof()
+    }
+}
+
+impl Reset for KangarooTwelve<'_> {
+    /// Reset the hasher, keeping the current customization string.
+    fn reset(&mut self) {
+        self.reset_keeping_customization();
+    }
+}
+
+/// The sponge states are zeroized on drop. The customization string is
+/// borrowed and left to its owner, and copies left behind by moving the
+/// hasher are not covered.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for KangarooTwelve<'_> {}
+
+/// Hash each of `messages` without customization into the corresponding
+/// element of `digests`.
+///
+/// With the `parallel` or `threads` feature, the messages are spread across
+/// threads, each being hashed on a single one.
+///
+/// # Panics
+///
+/// If there are not as many digests as messages.
+pub fn hash_batch_into(messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+    assert_eq!(
+        messages.len(),
+        digests.len(),
+        "there must be one digest per message"
+    );
+
+    #[cfg(feature = "parallel")]
+    parallel::hash_batch(messages, digests);
+    #[cfg(all(feature = "threads", not(feature = "parallel")))]
+    threads::hash_batch(messages, digests);
+    #[cfg(not(any(feature = "parallel", feature = "threads")))]
+    hash_messages(messages, digests);
+}
+
+/// Hash each of `messages` without customization into a 32-byte digest.
+///
+/// See [`hash_batch_into`].
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub fn hash_batch(messages: &[&[u8]]) -> Vec<[u8; 32]> {
+    let mut digests = vec![[0u8; 32]; messages.len()];
+    hash_batch_into(messages, &mut digests);
+    digests
+}
+
+/// Hash each of `messages` into the corresponding element of `digests` on
+/// the current thread.
+fn hash_messages(messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+    for (message, digest) in messages.iter().zip(digests) {
+        let mut h = KangarooTwelve::new();
+        #[cfg(any(feature = "parallel", feature = "threads"))]
+        {
+            h = h.with_threads(1);
         }
+        h.update(message);
+        *digest = h.finalize_xof_array();
     }
-    output
 }
 
-fn keccak(state: &mut [u8; 200]) {
-    let mut lanes = [0u64; 25];
-    let mut y;
-    for x in 0..5 {
-        FOR5!(y, 5, {
-            let pos = 8 * (x + y);
-            lanes[x + y] = u64::from_le_bytes(state[pos..(pos + 8)].try_into().unwrap());
-        });
-    }
-    lanes::keccak(&mut lanes);
-    for x in 0..5 {
-        FOR5!(y, 5, {
-            let i = 8 * (x + y);
-            state[i..i + 8].copy_from_slice(&lanes[x + y].to_le_bytes());
-        });
+/// Compute the chaining values of consecutive whole chunks of a message
+/// into `cvs`, for [`KangarooTwelve::absorb_chaining_values`].
+///
+/// The chunks must not include the first one of the message, which is not
+/// a leaf.
+///
+/// # Panics
+///
+/// If `chunks` is not exactly `cvs.len()` chunks long.
+pub fn chaining_values(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    assert_eq!(
+        chunks.len() as u64,
+        cvs.len() as u64 * CHUNK_SIZE_U64,
+        "input must consist of one whole chunk per chaining value"
+    );
+    for (chunks, cvs) in chunks
+        .chunks(LEAF_BATCH * CHUNK_SIZE)
+        .zip(cvs.chunks_mut(LEAF_BATCH))
+    {
+        hash_leaves(chunks, cvs);
     }
 }
 
-fn right_encode(mut x: usize) -> Vec<u8> {
-    let mut slice = Vec::new();
-    while x > 0 {
-        slice.push((x % 256) as u8);
-        x /= 256;
+/// Hash each whole chunk of `chunks` as a leaf, writing its chaining value
+/// to the corresponding element of `cvs`, with the fastest backend.
+fn hash_leaves(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    Backend::detect().hash_leaves(chunks, cvs);
+}
+
+/// Compute the chaining value of a whole chunk.
+fn leaf_chaining_value(chunk: &[u8]) -> [u8; CHAINING_VALUE_SIZE] {
+    tree::leaf_chaining_value::<RATE, CHAINING_VALUE_SIZE, ROUNDS>(chunk)
+}
+
+/// Encode `x` as in `right_encode` from NIST SP 800-185, writing the
+/// result into `buffer`.
+fn right_encode(x: u64, buffer: &mut [u8; RIGHT_ENCODE_SIZE]) -> &[u8] {
+    let bytes = x.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    let len = bytes.len() - skip;
+    buffer[..len].copy_from_slice(&bytes[skip..]);
+    buffer[len] = len as u8;
+    &buffer[..=len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{right_encode, RIGHT_ENCODE_SIZE};
+
+    #[test]
+    fn right_encode_large() {
+        let mut buffer = [0u8; RIGHT_ENCODE_SIZE];
+        assert_eq!(right_encode(0, &mut buffer), [0]);
+        assert_eq!(right_encode(255, &mut buffer), [0xFF, 1]);
+        assert_eq!(right_encode(256, &mut buffer), [1, 0, 2]);
+        // Chunk counts beyond `u32::MAX`, i.e. messages of more than 32 TiB
+        assert_eq!(right_encode(1 << 32, &mut buffer), [1, 0, 0, 0, 0, 5]);
+        assert_eq!(
+            right_encode(0xFFFF_FFFF_FFFF_FFFF, &mut buffer),
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 8]
+        );
     }
-    slice.reverse();
-    let len = slice.len();
-    slice.push(len as u8);
-    slice
 }
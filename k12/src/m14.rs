@@ -0,0 +1,35 @@
+//! MarsupilamiFourteen, the variant of KangarooTwelve with 14 rounds and
+//! 256-bit security.
+
+use crate::{
+    generic::{TreeHasher, TreeReader},
+    kt256::CHAINING_VALUE_SIZE,
+    turbo_shake::RATE_256,
+};
+
+/// Number of rounds of the permutation of MarsupilamiFourteen.
+const ROUNDS: usize = 14;
+
+/// The MarsupilamiFourteen extendable-output function (XOF).
+///
+/// MarsupilamiFourteen is the sibling of [`KangarooTwelve`](crate::KangarooTwelve)
+/// defined in the KangarooTwelve paper ("KangarooTwelve: fast hashing based
+/// on Keccak-p", ACNS 2018). It uses the tree of [`Kt256`](crate::Kt256),
+/// with 64-byte chaining values and a capacity of 512 bits, on
+/// Keccak-p[1600, 14], for a larger safety margin than the 12 rounds of
+/// KangarooTwelve and KT256.
+///
+/// Leaves are always hashed one at a time on the calling thread.
+///
+/// ```
+/// use k12::{digest::{ExtendableOutput, Update, XofReader}, MarsupilamiFourteen};
+///
+/// let mut hasher = MarsupilamiFourteen::new();
+/// hasher.update(b"hello world");
+/// let mut output = [0u8; 64];
+/// hasher.finalize_xof().read(&mut output);
+/// ```
+pub type MarsupilamiFourteen<'cs> = TreeHasher<'cs, RATE_256, CHAINING_VALUE_SIZE, ROUNDS>;
+
+/// Extensible output reader of [`MarsupilamiFourteen`].
+pub type MarsupilamiFourteenReader = TreeReader<RATE_256, ROUNDS>;
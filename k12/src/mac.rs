@@ -0,0 +1,118 @@
+//! Keyed KangarooTwelve, with the key as the customization string.
+
+use crate::{KangarooTwelve, NO_CUSTOMIZATION};
+use core::{fmt, mem};
+use digest::{
+    consts::U32, crypto_common::KeySizeUser, ExtendableOutput, FixedOutput, FixedOutputReset, Key,
+    KeyInit, MacMarker, Output, OutputSizeUser, Reset, Update, XofReader,
+};
+
+/// Size of the keys of [`K12Mac`].
+const KEY_SIZE: usize = 32;
+
+/// KangarooTwelve keyed with its customization string, as a message
+/// authentication code.
+///
+/// The tag of a message `M` under the 32-byte key `K` is the first 32 bytes
+/// of `K12(M, K)`, so the message is hashed in a single pass, and like with
+/// [`KangarooTwelve`], on several threads where enabled. Tags are checked
+/// in constant time by [`Mac::verify`](digest::Mac::verify).
+///
+/// Use [`HopMac`](crate::HopMac) for keys of other lengths or to keep a
+/// customization string for domain separation.
+///
+/// ```
+/// use k12::{digest::Mac, K12Mac};
+///
+/// let key = [0x42; 32];
+/// let mut mac = K12Mac::new(&key.into());
+/// mac.update(b"hello world");
+/// let tag = mac.finalize().into_bytes();
+///
+/// let mut mac = K12Mac::new(&key.into());
+/// mac.update(b"hello world");
+/// assert!(mac.verify(&tag).is_ok());
+/// ```
+#[derive(Clone)]
+pub struct K12Mac {
+    /// Hasher of the message, without customization until finalization
+    hasher: KangarooTwelve<'static>,
+
+    /// Key, applied as the customization string
+    key: [u8; KEY_SIZE],
+}
+
+impl K12Mac {
+    /// Compute the tag of the message absorbed so far into `out`, leaving
+    /// the hasher ready for a new message.
+    fn finalize_tag(&mut self, out: &mut Output<Self>) {
+        let fresh = self.hasher.fresh(NO_CUSTOMIZATION);
+        let mut hasher: KangarooTwelve<'_> = mem::replace(&mut self.hasher, fresh);
+        hasher.customization = &self.key;
+        hasher.finalize_xof().read(out);
+    }
+}
+
+impl KeySizeUser for K12Mac {
+    type KeySize = U32;
+}
+
+impl KeyInit for K12Mac {
+    fn new(key: &Key<Self>) -> Self {
+        let mut bytes = [0u8; KEY_SIZE];
+        bytes.copy_from_slice(key);
+        Self {
+            hasher: KangarooTwelve::new(),
+            key: bytes,
+        }
+    }
+}
+
+impl Update for K12Mac {
+    fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+}
+
+impl OutputSizeUser for K12Mac {
+    type OutputSize = U32;
+}
+
+impl FixedOutput for K12Mac {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.finalize_tag(out);
+    }
+}
+
+impl FixedOutputReset for K12Mac {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        self.finalize_tag(out);
+    }
+}
+
+impl Reset for K12Mac {
+    /// Reset the MAC, keeping the key.
+    fn reset(&mut self) {
+        self.hasher.reset();
+    }
+}
+
+impl MacMarker for K12Mac {}
+
+impl fmt::Debug for K12Mac {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("K12Mac { ... }")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for K12Mac {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.key);
+    }
+}
+
+/// The key and sponge states are zeroized on drop.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for K12Mac {}
@@ -0,0 +1,89 @@
+//! Hashing of several leaves at once, with their Keccak-p states
+//! interleaved lane by lane.
+
+use crate::{turbo_shake::RATE, CHAINING_VALUE_SIZE, CHUNK_SIZE, LEAF_DS};
+use core::convert::TryInto;
+
+/// Number of bytes of each chunk left for the padded final block.
+const TAIL: usize = CHUNK_SIZE % RATE;
+
+/// Hash `N` whole chunks as leaves in lockstep, writing their chaining
+/// values to `cvs`.
+#[cfg(any(test, target_feature = "avx512f"))]
+pub(crate) fn hash_leaves<const N: usize>(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    hash_leaves_with(chunks, cvs, crate::lanes::keccak_x::<N>);
+}
+
+/// Like [`hash_leaves`], but with the interleaved permutation `permute`.
+#[inline(always)]
+pub(crate) fn hash_leaves_with<P, const N: usize>(
+    chunks: &[u8],
+    cvs: &mut [[u8; CHAINING_VALUE_SIZE]],
+    mut permute: P,
+) where
+    P: FnMut(&mut [[u64; N]; 25]),
+{
+    debug_assert_eq!(chunks.len(), N * CHUNK_SIZE);
+    debug_assert_eq!(cvs.len(), N);
+
+    let mut state = [[0u64; N]; 25];
+    let mut offset = 0;
+    while offset + RATE <= CHUNK_SIZE {
+        absorb(&mut state, chunks, offset, RATE);
+        permute(&mut state);
+        offset += RATE;
+    }
+
+    // The tail is a whole number of lanes, so the padding starts a new lane
+    absorb(&mut state, chunks, offset, TAIL);
+    for lane in &mut state[TAIL / 8] {
+        *lane ^= u64::from(LEAF_DS);
+    }
+    for lane in &mut state[RATE / 8 - 1] {
+        *lane ^= 0x80 << 56;
+    }
+    permute(&mut state);
+
+    for (i, cv) in cvs.iter_mut().enumerate() {
+        for (lane, bytes) in state.iter().zip(cv.chunks_exact_mut(8)) {
+            bytes.copy_from_slice(&lane[i].to_le_bytes());
+        }
+    }
+}
+
+/// XOR `len` bytes at `offset` of each chunk into its interleaved state.
+fn absorb<const N: usize>(state: &mut [[u64; N]; 25], chunks: &[u8], offset: usize, len: usize) {
+    for (i, chunk) in chunks.chunks_exact(CHUNK_SIZE).enumerate() {
+        let words = chunk[offset..offset + len].chunks_exact(8);
+        for (lane, word) in state.iter_mut().zip(words) {
+            lane[i] ^= u64::from_le_bytes(word.try_into().unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hash_leaves;
+    use crate::{leaf_chaining_value, CHAINING_VALUE_SIZE, CHUNK_SIZE};
+
+    fn check<const N: usize>() {
+        let mut input = [0u8; 8 * CHUNK_SIZE];
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+        let chunks = &input[..N * CHUNK_SIZE];
+        let mut cvs = [[0u8; CHAINING_VALUE_SIZE]; N];
+        hash_leaves::<N>(chunks, &mut cvs);
+        for (chunk, cv) in chunks.chunks_exact(CHUNK_SIZE).zip(&cvs) {
+            assert_eq!(*cv, leaf_chaining_value(chunk));
+        }
+    }
+
+    #[test]
+    fn matches_single_leaf() {
+        check::<1>();
+        check::<2>();
+        check::<4>();
+        check::<8>();
+    }
+}
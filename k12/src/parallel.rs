@@ -0,0 +1,59 @@
+//! Parallel leaf hashing with rayon.
+
+use crate::{Backend, CHAINING_VALUE_SIZE, CHUNK_SIZE, LEAF_DEGREE};
+use rayon::prelude::*;
+
+/// Minimum number of groups of leaves hashed in lockstep worth handing to
+/// each rayon job.
+///
+/// A backend takes about as long to hash a group as the scalar one to hash
+/// a single leaf, so the fixed cost of a job is outweighed by a number of
+/// groups rather than of leaves. The crossover is measured by the
+/// `parallel` benchmarks.
+const MIN_GROUPS_PER_JOB: usize = 2;
+
+/// Hash each whole chunk of `chunks` as a leaf, writing its chaining value
+/// to the corresponding element of `cvs`, with `backend` on at most
+/// `max_threads` threads unless that is 0.
+///
+/// Falls back to hashing on the current thread when there are too few
+/// leaves to outweigh the cost of scheduling them, so that small updates are
+/// never slowed down.
+pub(crate) fn hash_leaves(
+    backend: Backend,
+    chunks: &[u8],
+    cvs: &mut [[u8; CHAINING_VALUE_SIZE]],
+    max_threads: usize,
+) {
+    let min_leaves = MIN_GROUPS_PER_JOB * backend.degree();
+    if cvs.len() < 2 * min_leaves || max_threads == 1 {
+        return backend.hash_leaves(chunks, cvs);
+    }
+
+    // Keep groups of leaves hashed in lockstep together, and make no more
+    // jobs than there may be threads
+    let groups = (cvs.len() - 1) / LEAF_DEGREE + 1;
+    let mut min_len = (min_leaves - 1) / LEAF_DEGREE + 1;
+    if let Some(per_thread) = (groups - 1).checked_div(max_threads) {
+        min_len = min_len.max(per_thread + 1);
+    }
+    chunks
+        .par_chunks(LEAF_DEGREE * CHUNK_SIZE)
+        .zip(cvs.par_chunks_mut(LEAF_DEGREE))
+        .with_min_len(min_len)
+        .for_each(|(chunks, cvs)| backend.hash_leaves(chunks, cvs));
+}
+
+/// Hash each of `messages` into the corresponding element of `digests`,
+/// spreading the messages over the thread pool.
+pub(crate) fn hash_batch(messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+    messages
+        .par_iter()
+        .zip(digests.par_iter_mut())
+        .for_each(|(message, digest)| {
+            crate::hash_messages(
+                core::slice::from_ref(message),
+                core::slice::from_mut(digest),
+            )
+        });
+}
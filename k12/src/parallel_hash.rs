@@ -0,0 +1,373 @@
+//! ParallelHash from NIST SP 800-185, on the sponge of the KangarooTwelve
+//! tree with the 24 rounds of Keccak-f\[1600\].
+
+use crate::{turbo_shake::TurboShake, LEAF_BATCH};
+use core::{cmp::min, mem};
+use digest::{ExtendableOutput, ExtendableOutputReset, HashMarker, Reset, Update, XofReader};
+
+/// Number of rounds of Keccak-f\[1600\], used by cSHAKE.
+const ROUNDS: usize = 24;
+
+/// Function name `N` of the cSHAKE instance of the final node.
+const FUNCTION_NAME: &[u8] = b"ParallelHash";
+
+/// Domain separation byte of cSHAKE with a function name or customization.
+const CSHAKE_DS: u8 = 0x04;
+
+/// Domain separation byte of SHAKE, i.e. cSHAKE without either.
+const SHAKE_DS: u8 = 0x1F;
+
+/// Maximum size of the encodings of SP 800-185.
+pub(crate) const ENCODE_SIZE: usize = 9;
+
+/// ParallelHash with sponges of rate `R` and a digest of `CV` bytes per
+/// block.
+#[derive(Clone, Debug)]
+struct Core<const R: usize, const CV: usize> {
+    /// Final node with only `bytepad(...) || left_encode(B)` absorbed
+    initial: TurboShake<R, ROUNDS>,
+
+    /// Final node, absorbing the digests of the blocks
+    final_node: TurboShake<R, ROUNDS>,
+
+    /// Sponge absorbing the current, incomplete block
+    block: TurboShake<R, ROUNDS>,
+
+    /// Block size `B` in bytes
+    block_size: usize,
+
+    /// Number of bytes absorbed into the current block
+    pos: usize,
+
+    /// Number of blocks absorbed into the final node
+    blocks: u64,
+}
+
+impl<const R: usize, const CV: usize> Core<R, CV> {
+    fn new(block_size: usize, customization: &[u8]) -> Self {
+        assert!(block_size > 0, "the block size must be positive");
+
+        // bytepad(encode_string(N) || encode_string(S), R) || left_encode(B)
+        let mut initial = TurboShake::new();
+        let mut buffers = [[0u8; ENCODE_SIZE]; 3];
+        let [rate, name_len, customization_len] = &mut buffers;
+        let mut len = 0;
+        for bytes in &[
+            left_encode(R as u64, rate),
+            left_encode(FUNCTION_NAME.len() as u64 * 8, name_len),
+            FUNCTION_NAME,
+            left_encode(customization.len() as u64 * 8, customization_len),
+            customization,
+        ] {
+            initial.absorb(bytes);
+            len += bytes.len();
+        }
+        initial.absorb(&[0u8; R][..(R - len % R) % R]);
+        initial.absorb(left_encode(block_size as u64, rate));
+
+        Self {
+            final_node: initial.clone(),
+            initial,
+            block: TurboShake::new(),
+            block_size,
+            pos: 0,
+            blocks: 0,
+        }
+    }
+
+    fn absorb(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.pos == 0 && data.len() >= self.block_size {
+                // === Hash whole blocks straight from the input ===
+                let mut cvs = [[0u8; CV]; LEAF_BATCH];
+                let n = min(data.len() / self.block_size, LEAF_BATCH);
+                let (blocks, rest) = data.split_at(n * self.block_size);
+                hash_blocks::<R, CV>(blocks, self.block_size, &mut cvs[..n]);
+                for cv in &cvs[..n] {
+                    self.final_node.absorb(cv);
+                }
+                self.blocks += n as u64;
+                data = rest;
+            } else {
+                let take = min(self.block_size - self.pos, data.len());
+                let (bytes, rest) = data.split_at(take);
+                self.block.absorb(bytes);
+                self.pos += take;
+                if self.pos == self.block_size {
+                    self.finish_block();
+                }
+                data = rest;
+            }
+        }
+    }
+
+    fn finish_block(&mut self) {
+        let cv: [u8; CV] = block_digest(mem::take(&mut self.block));
+        self.final_node.absorb(&cv);
+        self.blocks += 1;
+        self.pos = 0;
+    }
+
+    /// Finish the final node for an output of `output_bits` bits, or 0 for
+    /// the XOF variant, leaving the hasher ready for a new message.
+    fn finalize(&mut self, output_bits: u64) -> TurboShake<R, ROUNDS> {
+        if self.pos != 0 {
+            self.finish_block();
+        }
+        let mut state = mem::replace(&mut self.final_node, self.initial.clone());
+        let mut buffer = [0u8; ENCODE_SIZE];
+        state.absorb(right_encode(self.blocks, &mut buffer));
+        state.absorb(right_encode(output_bits, &mut buffer));
+        state.finalize(CSHAKE_DS);
+        self.blocks = 0;
+        state
+    }
+
+    fn reset(&mut self) {
+        self.final_node = self.initial.clone();
+        self.block = TurboShake::new();
+        self.pos = 0;
+        self.blocks = 0;
+    }
+}
+
+/// Hash each whole block of `blocks`, writing its digest to the
+/// corresponding element of `cvs`, with the `parallel` feature on the rayon
+/// thread pool.
+fn hash_blocks<const R: usize, const CV: usize>(
+    blocks: &[u8],
+    block_size: usize,
+    cvs: &mut [[u8; CV]],
+) {
+    let digest = |(block, cv): (&[u8], &mut [u8; CV])| {
+        let mut sponge = TurboShake::<R, ROUNDS>::new();
+        sponge.absorb(block);
+        *cv = block_digest(sponge);
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        // Hand each job at least two KangarooTwelve chunks' worth of input,
+        // so that jobs cost much more than scheduling them
+        let min_len = 2 * crate::CHUNK_SIZE / block_size + 1;
+        if cvs.len() > min_len {
+            return blocks
+                .par_chunks_exact(block_size)
+                .zip(cvs.par_iter_mut())
+                .with_min_len(min_len)
+                .for_each(digest);
+        }
+    }
+
+    blocks
+        .chunks_exact(block_size)
+        .zip(cvs.iter_mut())
+        .for_each(digest);
+}
+
+/// Finalize the sponge of a block as SHAKE and squeeze its digest.
+fn block_digest<const R: usize, const CV: usize>(mut sponge: TurboShake<R, ROUNDS>) -> [u8; CV] {
+    let mut cv = [0u8; CV];
+    sponge.finalize(SHAKE_DS);
+    sponge.squeeze(&mut cv);
+    cv
+}
+
+/// Encode `x` as in `left_encode` from NIST SP 800-185, writing the result
+/// into `buffer`.
+pub(crate) fn left_encode(x: u64, buffer: &mut [u8; ENCODE_SIZE]) -> &[u8] {
+    let bytes = x.to_be_bytes();
+    let skip = min(bytes.iter().take_while(|&&b| b == 0).count(), 7);
+    let n = 8 - skip;
+    buffer[0] = n as u8;
+    buffer[1..=n].copy_from_slice(&bytes[skip..]);
+    &buffer[..=n]
+}
+
+/// Encode `x` as in `right_encode` from NIST SP 800-185, writing the result
+/// into `buffer`. Unlike the encoding of KangarooTwelve, 0 is encoded on
+/// one byte as well.
+fn right_encode(x: u64, buffer: &mut [u8; ENCODE_SIZE]) -> &[u8] {
+    let bytes = x.to_be_bytes();
+    let skip = min(bytes.iter().take_while(|&&b| b == 0).count(), 7);
+    let n = 8 - skip;
+    buffer[..n].copy_from_slice(&bytes[skip..]);
+    buffer[n] = n as u8;
+    &buffer[..=n]
+}
+
+macro_rules! parallel_hash {
+    (
+        $(#[$attr:meta])* $name:ident,
+        $(#[$reader_attr:meta])* $reader:ident,
+        $rate:expr,
+        $cv:expr
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug)]
+        pub struct $name {
+            core: Core<$rate, $cv>,
+        }
+
+        impl $name {
+            /// Create a new instance with a block size of `block_size` bytes
+            /// and the customization string `customization`.
+            ///
+            /// # Panics
+            ///
+            /// If `block_size` is 0.
+            pub fn new(block_size: usize, customization: &[u8]) -> Self {
+                Self {
+                    core: Core::new(block_size, customization),
+                }
+            }
+
+            /// Finalize the fixed-length function, whose output depends on
+            /// its length, into `output`.
+            pub fn finalize_into(mut self, output: &mut [u8]) {
+                self.core
+                    .finalize(output.len() as u64 * 8)
+                    .squeeze(output);
+            }
+        }
+
+        impl HashMarker for $name {}
+
+        impl Update for $name {
+            fn update(&mut self, data: &[u8]) {
+                self.core.absorb(data);
+            }
+        }
+
+        #[cfg(feature = "std")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+        impl std::io::Write for $name {
+            #[inline]
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            #[inline]
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        /// Finalization as an XOF is the ParallelHashXOF variant.
+        impl ExtendableOutput for $name {
+            type Reader = $reader;
+
+            fn finalize_xof(mut self) -> Self::Reader {
+                self.finalize_xof_reset()
+            }
+        }
+
+        impl ExtendableOutputReset for $name {
+            fn finalize_xof_reset(&mut self) -> Self::Reader {
+                $reader {
+                    state: self.core.finalize(0),
+                }
+            }
+        }
+
+        impl Reset for $name {
+            /// Reset the hasher, keeping the block size and customization
+            /// string.
+            fn reset(&mut self) {
+                self.core.reset();
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+        impl zeroize::ZeroizeOnDrop for $name {}
+
+        $(#[$reader_attr])*
+        #[derive(Clone, Debug)]
+        pub struct $reader {
+            /// Sponge state at the current position
+            state: TurboShake<$rate, ROUNDS>,
+        }
+
+        impl XofReader for $reader {
+            fn read(&mut self, output: &mut [u8]) {
+                self.state.squeeze(output);
+            }
+        }
+
+        #[cfg(feature = "std")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+        impl std::io::Read for $reader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.state.squeeze(buf);
+                Ok(buf.len())
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+        impl zeroize::ZeroizeOnDrop for $reader {}
+    };
+}
+
+parallel_hash!(
+    /// ParallelHash128 from NIST SP 800-185, with 128-bit security.
+    ///
+    /// The message is split into blocks of a size chosen at construction,
+    /// each hashed with SHAKE128 into 32 bytes, and their digests are
+    /// hashed with cSHAKE128. With the `parallel` feature, whole blocks
+    /// given to a single update are hashed on the rayon thread pool.
+    ///
+    /// [`finalize_into`](Self::finalize_into) computes ParallelHash128, whose
+    /// output depends on its length. The [`ExtendableOutput`] traits compute
+    /// ParallelHashXOF128 instead.
+    ///
+    /// ```
+    /// use k12::{digest::Update, ParallelHash128};
+    ///
+    /// let mut hasher = ParallelHash128::new(8192, b"my customization");
+    /// hasher.update(b"hello world");
+    /// let mut output = [0u8; 32];
+    /// hasher.finalize_into(&mut output);
+    /// ```
+    ParallelHash128,
+    /// Extensible output reader of ParallelHashXOF128, from
+    /// [`ParallelHash128`].
+    ParallelHash128Reader,
+    168,
+    32
+);
+
+parallel_hash!(
+    /// ParallelHash256 from NIST SP 800-185, with 256-bit security.
+    ///
+    /// The same as [`ParallelHash128`] with SHAKE256 and cSHAKE256, and
+    /// 64-byte block digests.
+    ParallelHash256,
+    /// Extensible output reader of ParallelHashXOF256, from
+    /// [`ParallelHash256`].
+    ParallelHash256Reader,
+    136,
+    64
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{left_encode, right_encode, ENCODE_SIZE};
+
+    #[test]
+    fn encodings() {
+        let mut buffer = [0u8; ENCODE_SIZE];
+        assert_eq!(left_encode(0, &mut buffer), [1, 0]);
+        assert_eq!(right_encode(0, &mut buffer), [0, 1]);
+        assert_eq!(left_encode(168, &mut buffer), [1, 168]);
+        assert_eq!(right_encode(256, &mut buffer), [1, 0, 2]);
+        assert_eq!(
+            left_encode(u64::MAX, &mut buffer),
+            [8, 255, 255, 255, 255, 255, 255, 255, 255]
+        );
+    }
+}
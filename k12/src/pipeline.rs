@@ -0,0 +1,198 @@
+//! Hashing of leaves on background threads while input is being absorbed.
+
+use crate::{leaf_chaining_value, KangarooTwelve, Reader, CHAINING_VALUE_SIZE, CHUNK_SIZE};
+use core::{cmp::min, fmt, mem, num::NonZeroUsize};
+use digest::{ExtendableOutput, Update};
+use std::{
+    collections::VecDeque,
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    vec::Vec,
+};
+
+/// Maximum number of chunks handed to workers per worker before waiting for
+/// their chaining values, bounding the memory used for in-flight chunks.
+const IN_FLIGHT_PER_WORKER: usize = 2;
+
+/// A chunk to hash, with its sequence number.
+type Job = (u64, Vec<u8>);
+
+/// A hashed chunk, returned for reuse along with its chaining value.
+type Done = (u64, Vec<u8>, [u8; CHAINING_VALUE_SIZE]);
+
+/// KangarooTwelve hasher handing each completed chunk to a pool of worker
+/// threads, so that absorbing input overlaps with hashing it.
+///
+/// Created by [`KangarooTwelve::pipelined`]. Unlike [`KangarooTwelve`],
+/// this copies the input into chunk-sized buffers, of which at most two per
+/// worker are in flight at a time. Updates only block when that many
+/// chunks are waiting to be hashed. The workers are stopped when the
+/// pipeline is finalized or dropped.
+pub struct Pipeline<'cs> {
+    /// Hasher absorbing `S_0` and the chaining values in order
+    hasher: KangarooTwelve<'cs>,
+
+    /// Chunk being filled, starting at a leaf boundary of `hasher`
+    chunk: Vec<u8>,
+
+    /// Buffers of hashed chunks available for reuse
+    spare: Vec<Vec<u8>>,
+
+    /// Chaining values of the chunks handed to workers that have not been
+    /// absorbed yet, in order
+    pending: VecDeque<Option<[u8; CHAINING_VALUE_SIZE]>>,
+
+    /// Sequence number of the first pending chunk
+    next: u64,
+
+    jobs: Option<Sender<Job>>,
+    done: Receiver<Done>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<'cs> Pipeline<'cs> {
+    pub(crate) fn new(hasher: KangarooTwelve<'cs>, workers: usize) -> Self {
+        let workers = match workers {
+            0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            n => n,
+        };
+
+        let (jobs, job_queue) = channel::<Job>();
+        let job_queue = Arc::new(Mutex::new(job_queue));
+        let (done_sender, done) = channel();
+        let workers = (0..workers)
+            .map(|_| {
+                let job_queue = Arc::clone(&job_queue);
+                let done = done_sender.clone();
+                thread::spawn(move || loop {
+                    // The lock is released before hashing
+                    let job = job_queue.lock().unwrap().recv();
+                    let (seq, chunk) = match job {
+                        Ok(job) => job,
+                        Err(_) => return,
+                    };
+                    let cv = leaf_chaining_value(&chunk);
+                    if done.send((seq, chunk, cv)).is_err() {
+                        return;
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            hasher,
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+            spare: Vec::new(),
+            pending: VecDeque::new(),
+            next: 0,
+            jobs: Some(jobs),
+            done,
+            workers,
+        }
+    }
+
+    /// Hand the filled chunk to the workers, waiting for one to finish if
+    /// too many are in flight.
+    fn send_chunk(&mut self) {
+        let buffer = self
+            .spare
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(CHUNK_SIZE));
+        let chunk = mem::replace(&mut self.chunk, buffer);
+        let seq = self.next + self.pending.len() as u64;
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send((seq, chunk))
+            .expect("KangarooTwelve worker thread panicked");
+        self.pending.push_back(None);
+
+        while let Ok(done) = self.done.try_recv() {
+            self.receive(done);
+        }
+        while self.pending.len() > IN_FLIGHT_PER_WORKER * self.workers.len() {
+            self.receive_blocking();
+        }
+    }
+
+    fn receive_blocking(&mut self) {
+        let done = self
+            .done
+            .recv()
+            .expect("KangarooTwelve worker thread panicked");
+        self.receive(done);
+    }
+
+    /// Record a chaining value and absorb all those now available in order.
+    fn receive(&mut self, (seq, mut chunk, cv): Done) {
+        self.pending[(seq - self.next) as usize] = Some(cv);
+        chunk.clear();
+        self.spare.push(chunk);
+
+        while let Some(&Some(cv)) = self.pending.front() {
+            self.hasher.absorb_chaining_values(&[cv]);
+            self.pending.pop_front();
+            self.next += 1;
+        }
+    }
+}
+
+impl Update for Pipeline<'_> {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let boundary = self.hasher.leaf_boundary_distance();
+            if self.pending.is_empty() && self.chunk.is_empty() && boundary != 0 {
+                // Absorb directly until the next leaf can be handed out
+                let take = min(boundary, data.len());
+                self.hasher.update(&data[..take]);
+                data = &data[take..];
+                continue;
+            }
+
+            let take = min(CHUNK_SIZE - self.chunk.len(), data.len());
+            self.chunk.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.chunk.len() == CHUNK_SIZE {
+                self.send_chunk();
+            }
+        }
+    }
+}
+
+impl ExtendableOutput for Pipeline<'_> {
+    type Reader = Reader;
+
+    fn finalize_xof(mut self) -> Reader {
+        while !self.pending.is_empty() {
+            self.receive_blocking();
+        }
+        let mut hasher = mem::take(&mut self.hasher);
+        hasher.update(&self.chunk);
+        hasher.finalize_xof()
+    }
+}
+
+impl Drop for Pipeline<'_> {
+    fn drop(&mut self) {
+        // Closing the job queue stops the workers
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            // A panic has already been reported by the worker
+            let _ = worker.join();
+        }
+    }
+}
+
+impl fmt::Debug for Pipeline<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("hasher", &self.hasher)
+            .field("buffered", &self.chunk.len())
+            .field("in_flight", &self.pending.len())
+            .field("workers", &self.workers.len())
+            .finish()
+    }
+}
@@ -0,0 +1,419 @@
+//! Extensible output reader and adapters over it.
+
+use crate::turbo_shake::{Sponge128, RATE};
+use core::{cmp::min, mem};
+use digest::{InvalidOutputSize, XofReader};
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "uninit")]
+use core::mem::MaybeUninit;
+
+/// Extensible output reader.
+///
+/// The tree is fully processed by
+/// [`finalize_xof`](digest::ExtendableOutput::finalize_xof), so
+/// the reader only holds the sponge state output is squeezed from.
+/// Consecutive calls to [`XofReader::read`] continue the same output
+/// stream, and cloning a reader forks the stream at the current position.
+#[derive(Clone, Debug)]
+pub struct Reader {
+    /// Sponge state at the start of the output stream
+    initial: Sponge128,
+
+    /// Sponge state at the current position
+    state: Sponge128,
+
+    /// Number of output bytes preceding the current position
+    position: u64,
+}
+
+impl Reader {
+    pub(crate) fn new(state: Sponge128) -> Self {
+        Self {
+            initial: state.clone(),
+            state,
+            position: 0,
+        }
+    }
+
+    /// Fill `output` with the upcoming output bytes without consuming them.
+    ///
+    /// The following read returns the same bytes.
+    pub fn peek(&self, output: &mut [u8]) {
+        self.state.clone().squeeze(output);
+    }
+
+    /// Number of output bytes squeezed or skipped so far, i.e. the current
+    /// position in the output stream.
+    ///
+    /// The position saturates at `u64::MAX`, so it stops being accurate,
+    /// and [`seek`](Self::seek) exact, past 16 EiB of output.
+    pub fn bytes_read(&self) -> u64 {
+        self.position
+    }
+
+    /// Move to byte offset `pos` of the output stream.
+    ///
+    /// Seeking forward squeezes and discards the intermediate output one
+    /// block at a time; seeking backward restarts from the beginning of the
+    /// output stream.
+    pub fn seek(&mut self, pos: u64) {
+        if pos < self.position {
+            self.rewind();
+        }
+        self.skip(pos - self.position);
+    }
+
+    /// Discard the next `n` bytes of output without writing them anywhere.
+    pub fn skip(&mut self, n: u64) {
+        self.state.skip(n);
+        self.position = self.position.saturating_add(n);
+    }
+
+    /// Move back to the beginning of the output stream.
+    pub fn rewind(&mut self) {
+        self.state = self.initial.clone();
+        self.position = 0;
+    }
+
+    /// Iterate over the following output bytes.
+    pub fn bytes(&mut self) -> Bytes<'_> {
+        Bytes { reader: self }
+    }
+
+    /// Iterate over the following output in `N`-byte arrays.
+    pub fn chunks<const N: usize>(&mut self) -> Chunks<'_, N> {
+        Chunks { reader: self }
+    }
+
+    /// Limit the output stream to its next `n` bytes.
+    ///
+    /// With the `std` feature the returned [`Take`] implements
+    /// [`io::Read`](std::io::Read), reporting end-of-stream at the limit.
+    pub fn limit(self, n: u64) -> Take {
+        Take {
+            reader: self,
+            limit: n,
+        }
+    }
+
+    /// Wrap the reader in a [`BufferedReader`], which squeezes a whole
+    /// rate-sized block at a time and serves small reads from it, e.g. for
+    /// [`BufRead`](std::io::BufRead) with the `std` feature.
+    pub fn buffered(self) -> BufferedReader {
+        BufferedReader {
+            reader: self,
+            block: [0u8; RATE],
+            pos: RATE,
+        }
+    }
+
+    /// Squeeze the next `N` bytes of output into an array.
+    pub fn read_array<const N: usize>(&mut self) -> [u8; N] {
+        let mut output = [0u8; N];
+        self.squeeze(&mut output);
+        output
+    }
+
+    /// Squeeze the next `n` bytes of output into a newly allocated vector.
+    ///
+    /// See also [`XofReader::read_boxed`].
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+    pub fn read_vec(&mut self, n: usize) -> Vec<u8> {
+        let mut output = vec![0u8; n];
+        self.squeeze(&mut output);
+        output
+    }
+
+    /// Squeeze output into possibly uninitialized memory and return it as an
+    /// initialized slice, saving the cost of zeroing a large buffer first.
+    #[cfg(feature = "uninit")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "uninit")))]
+    #[allow(unsafe_code)]
+    pub fn read_uninit<'o>(&mut self, output: &'o mut [MaybeUninit<u8>]) -> &'o mut [u8] {
+        self.state.squeeze_uninit(output);
+        self.position = self.position.saturating_add(output.len() as u64);
+
+        // SAFETY: every element of `output` has been initialized, and
+        // MaybeUninit<u8> and u8 have exactly the same memory layout
+        unsafe { &mut *(output as *mut [MaybeUninit<u8>] as *mut [u8]) }
+    }
+
+    /// Squeeze output into each of `bufs` in turn, as if they were a single
+    /// contiguous buffer.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn read_vectored(&mut self, bufs: &mut [std::io::IoSliceMut<'_>]) {
+        for buf in bufs {
+            self.squeeze(buf);
+        }
+    }
+
+    /// Squeeze the next `len` bytes of output into `writer`, one rate-sized
+    /// block at a time.
+    ///
+    /// Output is consumed even if writing it fails.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn squeeze_into_writer<W: std::io::Write>(
+        &mut self,
+        writer: &mut W,
+        mut len: u64,
+    ) -> std::io::Result<()> {
+        let mut block = [0u8; RATE];
+        while len > 0 {
+            let n = min(len, RATE as u64) as usize;
+            self.squeeze(&mut block[..n]);
+            writer.write_all(&block[..n])?;
+            len -= n as u64;
+        }
+        Ok(())
+    }
+
+    fn squeeze(&mut self, output: &mut [u8]) {
+        self.state.squeeze(output);
+        self.position = self.position.saturating_add(output.len() as u64);
+    }
+}
+
+impl XofReader for Reader {
+    fn read(&mut self, output: &mut [u8]) {
+        self.squeeze(output);
+    }
+}
+
+/// Reader over the same output stream as a [`Reader`], optimized for many
+/// small reads.
+///
+/// Created by [`Reader::buffered`].
+#[derive(Clone, Debug)]
+pub struct BufferedReader {
+    reader: Reader,
+    block: [u8; RATE],
+
+    /// Position of the first unread byte of `block`
+    pos: usize,
+}
+
+impl BufferedReader {
+    /// Unwrap the underlying reader, discarding any buffered output.
+    pub fn into_inner(mut self) -> Reader {
+        // Leave a placeholder behind, as `BufferedReader` may implement `Drop`
+        mem::replace(&mut self.reader, Reader::new(Sponge128::new()))
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for BufferedReader {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.block);
+    }
+}
+
+impl XofReader for BufferedReader {
+    fn read(&mut self, mut output: &mut [u8]) {
+        while !output.is_empty() {
+            if self.pos == RATE {
+                if output.len() >= RATE {
+                    self.reader.squeeze(output);
+                    return;
+                }
+                self.reader.squeeze(&mut self.block);
+                self.pos = 0;
+            }
+
+            let n = min(RATE - self.pos, output.len());
+            let (head, rest) = output.split_at_mut(n);
+            head.copy_from_slice(&self.block[self.pos..self.pos + n]);
+            self.pos += n;
+            output = rest;
+        }
+    }
+}
+
+/// Reading never fails and never reaches the end of the stream.
+///
+/// As [`XofReader`] also has a `read` method, call this one as
+/// `io::Read::read(&mut reader, buf)` when both traits are in scope.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::io::Read for Reader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.squeeze(buf);
+        Ok(buf.len())
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [std::io::IoSliceMut<'_>]) -> std::io::Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        Reader::read_vectored(self, bufs);
+        Ok(len)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        self.squeeze(buf);
+        Ok(())
+    }
+}
+
+/// Reading never fails and never reaches the end of the stream, as for
+/// [`Reader`].
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::io::Read for BufferedReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        XofReader::read(self, buf);
+        Ok(buf.len())
+    }
+}
+
+/// The buffer holds the rest of the block squeezed last, so it never
+/// exceeds 168 bytes and is never empty.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::io::BufRead for BufferedReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos == RATE {
+            self.reader.squeeze(&mut self.block);
+            self.pos = 0;
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = min(self.pos + amt, RATE);
+    }
+}
+
+/// The output stream of a [`Reader`] as a deterministic random number
+/// generator.
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+impl rand_core::RngCore for Reader {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.squeeze(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.squeeze(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand_core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rand_core")))]
+impl rand_core::CryptoRng for Reader {}
+
+/// The sponge states are zeroized on drop.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for Reader {}
+
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for Take {}
+
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for BufferedReader {}
+
+/// Infinite iterator over the output bytes of a [`Reader`].
+///
+/// Created by [`Reader::bytes`].
+#[derive(Debug)]
+pub struct Bytes<'r> {
+    reader: &'r mut Reader,
+}
+
+impl Iterator for Bytes<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let mut byte = [0u8; 1];
+        self.reader.squeeze(&mut byte);
+        Some(byte[0])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Infinite iterator over the output of a [`Reader`] in `N`-byte arrays.
+///
+/// Created by [`Reader::chunks`].
+#[derive(Debug)]
+pub struct Chunks<'r, const N: usize> {
+    reader: &'r mut Reader,
+}
+
+impl<const N: usize> Iterator for Chunks<'_, N> {
+    type Item = [u8; N];
+
+    fn next(&mut self) -> Option<[u8; N]> {
+        Some(self.reader.read_array())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Output stream of a [`Reader`] limited to a fixed number of bytes.
+///
+/// Created by [`Reader::limit`].
+#[derive(Clone, Debug)]
+pub struct Take {
+    reader: Reader,
+    limit: u64,
+}
+
+impl Take {
+    /// Squeeze output into `output` until it is full or the limit is
+    /// reached, and return the number of bytes written.
+    ///
+    /// Returns 0 once the limit has been reached.
+    pub fn read(&mut self, output: &mut [u8]) -> usize {
+        let n = min(self.limit, output.len() as u64) as usize;
+        self.reader.squeeze(&mut output[..n]);
+        self.limit -= n as u64;
+        n
+    }
+
+    /// Fill `output` entirely, or fail without consuming any output if that
+    /// would exceed the limit.
+    pub fn try_read(&mut self, output: &mut [u8]) -> Result<(), InvalidOutputSize> {
+        if output.len() as u64 > self.limit {
+            return Err(InvalidOutputSize);
+        }
+        self.read(output);
+        Ok(())
+    }
+
+    /// Number of bytes that can still be read.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Unwrap the underlying reader, which is not limited.
+    pub fn into_inner(self) -> Reader {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::io::Read for Take {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(Take::read(self, buf))
+    }
+}
@@ -0,0 +1,78 @@
+//! Deterministic random number generator on the KangarooTwelve output
+//! stream.
+
+use crate::{labels, KangarooTwelve, Reader};
+use core::fmt;
+use digest::{ExtendableOutput, Update};
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+
+/// Deterministic random number generator seeded with arbitrary bytes.
+///
+/// The output is the stream `K12(seed, "KangarooTwelve RNG")`, squeezed as
+/// it is requested, so equal seeds give equal sequences on every platform
+/// and unequal seeds independent ones. Seeds of any length are accepted by
+/// [`from_seed_bytes`](Self::from_seed_bytes); those of
+/// [`SeedableRng::from_seed`] are 32 bytes.
+///
+/// For secret output the seed must have at least 128 bits of entropy.
+///
+/// ```
+/// use k12::{rand_core::RngCore, K12Rng};
+///
+/// let mut rng = K12Rng::from_seed_bytes(b"simulation run 17");
+/// let roll = rng.next_u32() % 6 + 1;
+/// ```
+#[derive(Clone)]
+pub struct K12Rng {
+    /// Output stream at the current position
+    reader: Reader,
+}
+
+impl K12Rng {
+    /// Create a generator seeded with `seed`, of any length.
+    pub fn from_seed_bytes(seed: &[u8]) -> Self {
+        let reader = KangarooTwelve::new_with_customization(labels::RNG)
+            .chain(seed)
+            .finalize_xof();
+        Self { reader }
+    }
+}
+
+impl SeedableRng for K12Rng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_seed_bytes(&seed)
+    }
+}
+
+impl RngCore for K12Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.reader.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.reader.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.reader.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.reader.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for K12Rng {}
+
+impl fmt::Debug for K12Rng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("K12Rng { ... }")
+    }
+}
+
+/// The generator state is zeroized on drop.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for K12Rng {}
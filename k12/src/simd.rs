@@ -0,0 +1,92 @@
+//! Keccak-p[1600, 12] on two interleaved states using `core::simd`, for any
+//! target with 128-bit vectors, without code specific to its instruction set.
+
+use crate::{
+    lanes::{PI, RC, RHO},
+    multi_buffer, CHAINING_VALUE_SIZE,
+};
+use core::simd::u64x2;
+
+/// Hash 2 whole chunks as leaves in lockstep, writing their chaining values
+/// to `cvs`.
+pub(crate) fn hash_leaves_x2(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    multi_buffer::hash_leaves_with(chunks, cvs, keccak_x2);
+}
+
+fn keccak_x2(lanes: &mut [[u64; 2]; 25]) {
+    let mut s = [u64x2::splat(0); 25];
+    for (s, lane) in s.iter_mut().zip(lanes.iter()) {
+        *s = u64x2::from_array(*lane);
+    }
+
+    let mut c = [u64x2::splat(0); 5];
+    let (mut x, mut y): (usize, usize);
+
+    for &rc in RC.iter() {
+        // θ
+        FOR5!(x, 1, {
+            c[x] = s[x] ^ s[x + 5] ^ s[x + 10] ^ s[x + 15] ^ s[x + 20];
+        });
+
+        FOR5!(x, 1, {
+            let d = c[(x + 4) % 5] ^ rotl(c[(x + 1) % 5], 1);
+            FOR5!(y, 5, {
+                s[x + y] ^= d;
+            });
+        });
+
+        // ρ and π
+        let mut a = s[1];
+        x = 0;
+        REPEAT24!(
+            {
+                c[0] = s[PI[x]];
+                s[PI[x]] = rotl(a, RHO[x]);
+            },
+            {
+                a = c[0];
+                x += 1;
+            }
+        );
+
+        // χ
+        FOR5!(y, 5, {
+            FOR5!(x, 1, {
+                c[x] = s[x + y];
+            });
+            FOR5!(x, 1, {
+                s[x + y] = c[x] ^ (!c[(x + 1) % 5] & c[(x + 2) % 5]);
+            });
+        });
+
+        // ι
+        s[0] ^= u64x2::splat(rc);
+    }
+
+    for (s, lane) in s.iter().zip(lanes.iter_mut()) {
+        *lane = s.to_array();
+    }
+}
+
+#[inline(always)]
+fn rotl(a: u64x2, n: u32) -> u64x2 {
+    (a << u64::from(n)) | (a >> u64::from(64 - n))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{leaf_chaining_value, CHAINING_VALUE_SIZE, CHUNK_SIZE};
+
+    #[test]
+    fn matches_single_leaf() {
+        let mut input = [0u8; 2 * CHUNK_SIZE];
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+        let mut cvs = [[0u8; CHAINING_VALUE_SIZE]; 2];
+        super::hash_leaves_x2(&input, &mut cvs);
+        for (chunk, cv) in input.chunks_exact(CHUNK_SIZE).zip(&cvs) {
+            assert_eq!(*cv, leaf_chaining_value(chunk));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Keystream generation with KangarooTwelve.
+
+use crate::{labels, turbo_shake::RATE, KangarooTwelve, Reader};
+use core::fmt;
+use digest::{Update, XofReader};
+
+/// Unbounded keystream derived from a key and a nonce, for experiments with
+/// KangarooTwelve as a stream cipher.
+///
+/// The keystream is `K12(key, "KangarooTwelve stream" || nonce)`. The key is
+/// the message and the nonce is in the customization string, so no key and
+/// nonce pair is framed like another, and the keystream cannot be computed
+/// by hashing for any other purpose with the same key.
+///
+/// **This is not authenticated encryption.** Ciphertexts are malleable, and
+/// reusing a nonce with the same key reveals the XOR of the plaintexts.
+/// Keys should be at least 16 bytes of secret randomness.
+///
+/// ```
+/// use k12::K12Stream;
+///
+/// let (key, nonce) = ([0x42; 32], [0x24; 12]);
+/// let mut data = *b"hello world";
+/// K12Stream::new(&key, &nonce).apply_keystream(&mut data);
+/// K12Stream::new(&key, &nonce).apply_keystream(&mut data);
+/// assert_eq!(&data, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct K12Stream {
+    /// Keystream at the current position
+    reader: Reader,
+}
+
+impl K12Stream {
+    /// Create the keystream of `key` and `nonce`.
+    pub fn new(key: &[u8], nonce: &[u8]) -> Self {
+        let state = KangarooTwelve::new()
+            .chain(key)
+            .finalize_tree_with(&[labels::STREAM, nonce]);
+        Self {
+            reader: Reader::new(state),
+        }
+    }
+
+    /// XOR the next `data.len()` bytes of keystream into `data`.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        let mut keystream = [0u8; RATE];
+        for chunk in data.chunks_mut(RATE) {
+            let keystream = &mut keystream[..chunk.len()];
+            self.reader.read(keystream);
+            for (byte, k) in chunk.iter_mut().zip(keystream.iter()) {
+                *byte ^= k;
+            }
+        }
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut keystream);
+    }
+
+    /// Move to byte offset `pos` of the keystream, e.g. to decrypt part of a
+    /// message.
+    pub fn seek(&mut self, pos: u64) {
+        self.reader.seek(pos);
+    }
+
+    /// Current byte offset in the keystream.
+    pub fn position(&self) -> u64 {
+        self.reader.bytes_read()
+    }
+}
+
+impl XofReader for K12Stream {
+    /// Write the next `output.len()` bytes of keystream to `output`.
+    fn read(&mut self, output: &mut [u8]) {
+        self.reader.read(output);
+    }
+}
+
+impl fmt::Debug for K12Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("K12Stream { ... }")
+    }
+}
+
+/// The keystream state is zeroized on drop.
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+impl zeroize::ZeroizeOnDrop for K12Stream {}
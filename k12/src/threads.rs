@@ -0,0 +1,81 @@
+//! Parallel leaf hashing on scoped standard library threads.
+
+use crate::{Backend, CHAINING_VALUE_SIZE, CHUNK_SIZE, LEAF_DEGREE};
+use core::num::NonZeroUsize;
+use std::thread;
+
+/// Minimum number of groups of leaves hashed in lockstep worth handing to
+/// each thread.
+///
+/// A backend takes about as long to hash a group as the scalar one to hash
+/// a single leaf, so the fixed cost of spawning and joining a thread is
+/// outweighed by a number of groups rather than of leaves. The crossover is
+/// measured by the `parallel` benchmarks.
+const MIN_GROUPS_PER_THREAD: usize = 8;
+
+/// Hash each whole chunk of `chunks` as a leaf, writing its chaining value
+/// to the corresponding element of `cvs`, with `backend`.
+///
+/// The leaves are split evenly over `max_threads` threads, or one per
+/// available CPU if that is 0, one of which is the current thread. Each
+/// thread gets at least [`MIN_GROUPS_PER_THREAD`] groups of leaves. The threads are
+/// spawned once per call, so callers pass all the leaves they have at once.
+pub(crate) fn hash_leaves(
+    backend: Backend,
+    chunks: &[u8],
+    cvs: &mut [[u8; CHAINING_VALUE_SIZE]],
+    max_threads: usize,
+) {
+    let threads = match max_threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        n => n,
+    };
+    let threads = threads.min(cvs.len() / (MIN_GROUPS_PER_THREAD * backend.degree()));
+    if threads <= 1 {
+        return backend.hash_leaves(chunks, cvs);
+    }
+
+    // Keep groups of leaves hashed in lockstep together
+    let groups = (cvs.len() - 1) / LEAF_DEGREE + 1;
+    let per_thread = ((groups - 1) / threads + 1) * LEAF_DEGREE;
+    let mut parts = chunks
+        .chunks(per_thread * CHUNK_SIZE)
+        .zip(cvs.chunks_mut(per_thread));
+    let first = parts.next();
+    thread::scope(|scope| {
+        for (chunks, cvs) in parts {
+            scope.spawn(move || backend.hash_leaves(chunks, cvs));
+        }
+        if let Some((chunks, cvs)) = first {
+            backend.hash_leaves(chunks, cvs);
+        }
+    });
+}
+
+/// Hash each of `messages` into the corresponding element of `digests`,
+/// spreading the messages evenly over at most one thread per available CPU
+/// and per [`MIN_GROUPS_PER_THREAD`] groups of chunks of input.
+pub(crate) fn hash_batch(messages: &[&[u8]], digests: &mut [[u8; 32]]) {
+    let cpus = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let len: usize = messages.iter().map(|message| message.len()).sum();
+    let threads = cpus
+        .min(messages.len())
+        .min(len / (MIN_GROUPS_PER_THREAD * Backend::detect().degree() * CHUNK_SIZE));
+    if threads <= 1 {
+        return crate::hash_messages(messages, digests);
+    }
+
+    let per_thread = (messages.len() - 1) / threads + 1;
+    let mut parts = messages
+        .chunks(per_thread)
+        .zip(digests.chunks_mut(per_thread));
+    let first = parts.next();
+    thread::scope(|scope| {
+        for (messages, digests) in parts {
+            scope.spawn(move || crate::hash_messages(messages, digests));
+        }
+        if let Some((messages, digests)) = first {
+            crate::hash_messages(messages, digests);
+        }
+    });
+}
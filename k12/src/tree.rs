@@ -0,0 +1,257 @@
+//! The KangarooTwelve tree, generic over the rate and number of rounds of
+//! its TurboSHAKE sponges and the size of its chaining values, so that it is
+//! shared by [`KangarooTwelve`](crate::KangarooTwelve), [`Kt256`](crate::Kt256)
+//! and [`MarsupilamiFourteen`](crate::MarsupilamiFourteen).
+
+use crate::{
+    right_encode, turbo_shake::TurboShake, CHUNK_SIZE, CHUNK_SIZE_U64, FINAL_NODE_DS,
+    FINAL_NODE_PRE, FINAL_NODE_SUF, LEAF_BATCH, LEAF_DS, RIGHT_ENCODE_SIZE, SINGLE_NODE_DS,
+};
+#[cfg(feature = "alloc")]
+use alloc::vec;
+use core::{cmp::min, mem};
+
+/// State of a tree being absorbed, with sponges of rate `R` on
+/// Keccak-p[1600, `ROUNDS`] and chaining values of `CV` bytes.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Tree<const R: usize, const CV: usize, const ROUNDS: usize> {
+    /// Sponge absorbing the final node
+    final_node: TurboShake<R, ROUNDS>,
+
+    /// Sponge absorbing the chunk currently being processed as a leaf
+    leaf: TurboShake<R, ROUNDS>,
+
+    /// Number of bytes of `S` absorbed so far.
+    ///
+    /// Always 64-bit so that messages above 4 GiB are framed correctly on
+    /// 32-bit targets as well.
+    len: u64,
+}
+
+impl<const R: usize, const CV: usize, const ROUNDS: usize> Tree<R, CV, ROUNDS> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            final_node: TurboShake::new(),
+            leaf: TurboShake::new(),
+            len: 0,
+        }
+    }
+
+    /// Number of bytes of `S` absorbed so far.
+    pub(crate) fn message_len(&self) -> u64 {
+        self.len
+    }
+
+    /// Number of bytes absorbed into the current, incomplete chunk.
+    pub(crate) fn buffered_len(&self) -> usize {
+        (self.len % CHUNK_SIZE_U64) as usize
+    }
+
+    /// Number of bytes to absorb before reaching the start of a leaf, or 0
+    /// if the tree is at one.
+    #[cfg(feature = "threads")]
+    pub(crate) fn leaf_boundary_distance(&self) -> usize {
+        if self.len < CHUNK_SIZE_U64 {
+            CHUNK_SIZE - self.len as usize
+        } else {
+            (CHUNK_SIZE - self.buffered_len()) % CHUNK_SIZE
+        }
+    }
+
+    /// Absorb bytes of the input string `S`.
+    ///
+    /// Whole chunks starting at a leaf boundary are hashed straight from
+    /// `bytes`, in batches of [`LEAF_BATCH`], by `hash_leaves`, which writes
+    /// the chaining value of each chunk to the corresponding element of its
+    /// second argument.
+    pub(crate) fn absorb<F>(&mut self, bytes: &[u8], hash_leaves: &F)
+    where
+        F: Fn(&[u8], &mut [[u8; CV]]),
+    {
+        self.absorb_batched(bytes, hash_leaves, LEAF_BATCH);
+    }
+
+    /// Absorb bytes of the input string `S` like [`absorb`](Self::absorb),
+    /// in batches of up to `max_leaves` whole chunks.
+    ///
+    /// With the `alloc` feature, the chaining values of batches larger than
+    /// [`LEAF_BATCH`] are collected on the heap, e.g. to hand all the whole
+    /// chunks of an update to threads at once.
+    pub(crate) fn absorb_batched<F>(&mut self, mut bytes: &[u8], hash_leaves: &F, max_leaves: usize)
+    where
+        F: Fn(&[u8], &mut [[u8; CV]]),
+    {
+        while !bytes.is_empty() {
+            let take = if self.len < CHUNK_SIZE_U64 {
+                // === Absorb `S_0` as the prefix of the final node ===
+                let take = min(CHUNK_SIZE - self.len as usize, bytes.len());
+                self.final_node.absorb(&bytes[..take]);
+                take
+            } else {
+                if self.len == CHUNK_SIZE_U64 {
+                    // The input spans more than one chunk
+                    self.final_node.absorb(&FINAL_NODE_PRE);
+                }
+
+                let pos = (self.len % CHUNK_SIZE_U64) as usize;
+                if pos == 0 && bytes.len() >= CHUNK_SIZE {
+                    // === Hash whole chunks `S_i` straight from the input ===
+                    self.absorb_leaves(bytes, hash_leaves, max_leaves)
+                } else {
+                    // === Absorb `S_i` into the current leaf ===
+                    let take = min(CHUNK_SIZE - pos, bytes.len());
+                    self.leaf.absorb(&bytes[..take]);
+
+                    if pos + take == CHUNK_SIZE {
+                        // Leaves are hashed as soon as they are complete
+                        self.finish_leaf();
+                    }
+                    take
+                }
+            };
+
+            self.len += take as u64;
+            bytes = &bytes[take..];
+        }
+    }
+
+    /// Hash a batch of up to `max_leaves` whole chunks from the start of
+    /// `bytes` as leaves with `hash_leaves` and absorb their chaining values
+    /// into the final node, returning the number of bytes consumed.
+    fn absorb_leaves<F>(&mut self, bytes: &[u8], hash_leaves: &F, max_leaves: usize) -> usize
+    where
+        F: Fn(&[u8], &mut [[u8; CV]]),
+    {
+        #[cfg(feature = "alloc")]
+        {
+            let n = min(bytes.len() / CHUNK_SIZE, max_leaves);
+            if n > LEAF_BATCH {
+                let take = n * CHUNK_SIZE;
+                let mut cvs = vec![[0u8; CV]; n];
+                hash_leaves(&bytes[..take], &mut cvs);
+                for cv in &cvs {
+                    self.final_node.absorb(cv);
+                }
+                return take;
+            }
+        }
+
+        let mut cvs = [[0u8; CV]; LEAF_BATCH];
+        let n = min(bytes.len() / CHUNK_SIZE, min(max_leaves, LEAF_BATCH));
+        let take = n * CHUNK_SIZE;
+        hash_leaves(&bytes[..take], &mut cvs[..n]);
+
+        for cv in &cvs[..n] {
+            self.final_node.absorb(cv);
+        }
+        take
+    }
+
+    /// Absorb the chaining values of whole chunks hashed elsewhere, as if the
+    /// chunks themselves had been absorbed.
+    ///
+    /// # Panics
+    ///
+    /// If the number of bytes absorbed so far is not a positive multiple of
+    /// [`CHUNK_SIZE`].
+    pub(crate) fn absorb_chaining_values(&mut self, cvs: &[[u8; CV]]) {
+        assert!(
+            self.len >= CHUNK_SIZE_U64 && self.buffered_len() == 0,
+            "chaining values must be absorbed at the start of a chunk after the first"
+        );
+        if cvs.is_empty() {
+            return;
+        }
+        if self.len == CHUNK_SIZE_U64 {
+            self.final_node.absorb(&FINAL_NODE_PRE);
+        }
+        for cv in cvs {
+            self.final_node.absorb(cv);
+        }
+        self.len += cvs.len() as u64 * CHUNK_SIZE_U64;
+    }
+
+    /// Compute the chaining value of the current leaf and absorb it into the
+    /// final node.
+    fn finish_leaf(&mut self) {
+        let cv: [u8; CV] = chaining_value(mem::take(&mut self.leaf));
+        self.final_node.absorb(&cv);
+    }
+
+    /// Absorb the customization string and finish the tree, returning the
+    /// finalized sponge output is squeezed from.
+    pub(crate) fn finalize<F>(self, customization: &[u8], hash_leaves: &F) -> TurboShake<R, ROUNDS>
+    where
+        F: Fn(&[u8], &mut [[u8; CV]]),
+    {
+        self.finalize_parts(&[customization], hash_leaves)
+    }
+
+    /// Finish the tree like [`finalize`](Self::finalize), with the
+    /// customization string given as the concatenation of `parts`.
+    pub(crate) fn finalize_parts<F>(
+        mut self,
+        parts: &[&[u8]],
+        hash_leaves: &F,
+    ) -> TurboShake<R, ROUNDS>
+    where
+        F: Fn(&[u8], &mut [[u8; CV]]),
+    {
+        let mut buffer = [0u8; RIGHT_ENCODE_SIZE];
+        let mut len = 0u64;
+        for part in parts {
+            self.absorb(part, hash_leaves);
+            len += part.len() as u64;
+        }
+        self.absorb(right_encode(len, &mut buffer), hash_leaves);
+
+        if self.len <= CHUNK_SIZE_U64 {
+            // === Process the tree with only a final node ===
+            self.final_node.finalize(SINGLE_NODE_DS);
+        } else {
+            // === Process the tree with kangaroo hopping ===
+            let pos = self.len % CHUNK_SIZE_U64;
+            if pos != 0 {
+                // Only the trailing partial leaf remains to be hashed
+                self.finish_leaf();
+            }
+
+            let n = (self.len - 1) / CHUNK_SIZE_U64;
+            self.final_node.absorb(right_encode(n, &mut buffer));
+            self.final_node.absorb(&FINAL_NODE_SUF);
+            self.final_node.finalize(FINAL_NODE_DS);
+        }
+
+        self.final_node
+    }
+}
+
+/// Hash each whole chunk of `chunks` as a leaf, one at a time, writing its
+/// chaining value to the corresponding element of `cvs`.
+pub(crate) fn hash_leaves_serial<const R: usize, const CV: usize, const ROUNDS: usize>(
+    chunks: &[u8],
+    cvs: &mut [[u8; CV]],
+) {
+    for (chunk, cv) in chunks.chunks_exact(CHUNK_SIZE).zip(cvs) {
+        *cv = leaf_chaining_value::<R, CV, ROUNDS>(chunk);
+    }
+}
+
+/// Compute the chaining value of a whole chunk.
+pub(crate) fn leaf_chaining_value<const R: usize, const CV: usize, const ROUNDS: usize>(
+    chunk: &[u8],
+) -> [u8; CV] {
+    let mut leaf = TurboShake::<R, ROUNDS>::new();
+    leaf.absorb(chunk);
+    chaining_value(leaf)
+}
+
+/// Finalize a leaf and squeeze its chaining value.
+pub(crate) fn chaining_value<const R: usize, const CV: usize, const ROUNDS: usize>(
+    mut leaf: TurboShake<R, ROUNDS>,
+) -> [u8; CV] {
+    let mut cv = [0u8; CV];
+    leaf.finalize(LEAF_DS);
+    leaf.squeeze(&mut cv);
+    cv
+}
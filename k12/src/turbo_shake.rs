@@ -0,0 +1,341 @@
+//! Incremental TurboSHAKE sponge used for both the leaves and the final
+//! node of the KangarooTwelve tree, and the TurboSHAKE XOFs built on it.
+
+use crate::lanes;
+use core::{cmp::min, convert::TryInto, fmt, mem};
+use digest::{ExtendableOutput, ExtendableOutputReset, HashMarker, Reset, Update, XofReader};
+
+#[cfg(feature = "uninit")]
+use core::mem::MaybeUninit;
+
+/// Number of 64-bit lanes in the Keccak-p[1600] state.
+const PLEN: usize = 25;
+
+/// Rate of TurboSHAKE128 in bytes.
+pub(crate) const RATE: usize = 168;
+
+/// Rate of TurboSHAKE256 in bytes.
+pub(crate) const RATE_256: usize = 136;
+
+/// Number of rounds of the permutation of TurboSHAKE.
+pub(crate) const ROUNDS: usize = 12;
+
+/// Sponge of TurboSHAKE128, used by KangarooTwelve.
+pub(crate) type Sponge128 = TurboShake<RATE, ROUNDS>;
+
+/// TurboSHAKE with a rate of `R` bytes, or with `ROUNDS` other than 12 the
+/// same sponge on Keccak-p[1600, `ROUNDS`].
+#[derive(Clone, Debug)]
+pub(crate) struct TurboShake<const R: usize, const ROUNDS: usize> {
+    /// Lanes converted by [`lanes::to_state`]
+    state: [u64; PLEN],
+
+    /// Position inside the current rate block
+    pos: usize,
+}
+
+impl<const R: usize, const ROUNDS: usize> Default for TurboShake<R, ROUNDS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const R: usize, const ROUNDS: usize> TurboShake<R, ROUNDS> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: [0u64; PLEN],
+            pos: 0,
+        }
+    }
+
+    /// Absorb `data` into the sponge.
+    ///
+    /// Input is XORed into the state directly from `data`, one lane at a
+    /// time wherever it is lane-aligned.
+    pub(crate) fn absorb(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = min(R - self.pos, data.len());
+            let (block, rest) = data.split_at(take);
+            self.xor_bytes(block);
+            self.pos += take;
+
+            if self.pos == R {
+                lanes::keccak(&mut self.state, ROUNDS);
+                self.pos = 0;
+            }
+            data = rest;
+        }
+    }
+
+    /// Apply the domain separation byte and padding, and switch to the
+    /// squeezing phase.
+    pub(crate) fn finalize(&mut self, domain_separation: u8) {
+        self.xor_byte(self.pos, domain_separation);
+        self.xor_byte(R - 1, 0x80);
+        lanes::keccak(&mut self.state, ROUNDS);
+        self.pos = 0;
+    }
+
+    /// Squeeze output from a finalized sponge.
+    pub(crate) fn squeeze(&mut self, output: &mut [u8]) {
+        self.squeeze_with(output, |byte| byte);
+    }
+
+    /// Squeeze output into possibly uninitialized memory, initializing all
+    /// of `output`.
+    #[cfg(feature = "uninit")]
+    pub(crate) fn squeeze_uninit(&mut self, output: &mut [MaybeUninit<u8>]) {
+        self.squeeze_with(output, MaybeUninit::new);
+    }
+
+    fn squeeze_with<T>(&mut self, output: &mut [T], wrap: impl Fn(u8) -> T) {
+        for out in output {
+            if self.pos == R {
+                lanes::keccak(&mut self.state, ROUNDS);
+                self.pos = 0;
+            }
+            *out = wrap(self.byte(self.pos));
+            self.pos += 1;
+        }
+    }
+
+    /// Discard the next `n` bytes of output from a finalized sponge.
+    pub(crate) fn skip(&mut self, n: u64) {
+        let available = (R - self.pos) as u64;
+        if n <= available {
+            self.pos += n as usize;
+            return;
+        }
+
+        // Permute once per block until reaching the one containing the new
+        // position, which is left in the same state `squeeze` would.
+        let n = n - available;
+        let rate = R as u64;
+        let blocks = (n - 1) / rate + 1;
+        for _ in 0..blocks {
+            lanes::keccak(&mut self.state, ROUNDS);
+        }
+        self.pos = (n - (blocks - 1) * rate) as usize;
+    }
+
+    /// XOR `bytes` into the state starting at the current position. They
+    /// must fit within the rate.
+    fn xor_bytes(&mut self, mut bytes: &[u8]) {
+        let mut pos = self.pos;
+
+        let offset = pos % 8;
+        if offset != 0 {
+            let n = min(8 - offset, bytes.len());
+            for &b in &bytes[..n] {
+                self.xor_byte(pos, b);
+                pos += 1;
+            }
+            bytes = &bytes[n..];
+        }
+
+        let mut words = bytes.chunks_exact(8);
+        for (word, s) in (&mut words).zip(self.state[pos / 8..].iter_mut()) {
+            *s ^= lanes::to_state(u64::from_le_bytes(word.try_into().unwrap()));
+            pos += 8;
+        }
+
+        for &b in words.remainder() {
+            self.xor_byte(pos, b);
+            pos += 1;
+        }
+    }
+
+    fn byte(&self, pos: usize) -> u8 {
+        lanes::state_byte(self.state[pos / 8], pos % 8)
+    }
+
+    fn xor_byte(&mut self, pos: usize, byte: u8) {
+        self.state[pos / 8] ^= lanes::to_state(u64::from(byte) << (8 * (pos % 8)));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<const R: usize, const ROUNDS: usize> Drop for TurboShake<R, ROUNDS> {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+        self.state.zeroize();
+        self.pos.zeroize();
+    }
+}
+
+/// Error creating a TurboSHAKE XOF with a domain separation byte outside
+/// `0x01..=0x7F`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidDomainSeparation(pub u8);
+
+impl fmt::Display for InvalidDomainSeparation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the domain separation byte {:#04x} is not in 0x01..=0x7F",
+            self.0
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidDomainSeparation {}
+
+macro_rules! turbo_shake_xof {
+    (
+        $(#[$attr:meta])* $name:ident,
+        $(#[$reader_attr:meta])* $reader:ident,
+        $rate:expr
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug)]
+        pub struct $name {
+            /// Sponge absorbing the message
+            sponge: TurboShake<$rate, ROUNDS>,
+
+            /// Domain separation byte `D` applied at finalization
+            domain_separation: u8,
+        }
+
+        impl $name {
+            /// Create a new instance with the domain separation byte `D`.
+            ///
+            /// Values of `D` in `0x01..=0x7F` are accepted. Those used by
+            /// KangarooTwelve's tree, `0x06`, `0x07` and `0x0B`, are best
+            /// avoided by other protocols.
+            pub fn new(domain_separation: u8) -> Result<Self, InvalidDomainSeparation> {
+                match domain_separation {
+                    0x01..=0x7F => Ok(Self {
+                        sponge: TurboShake::new(),
+                        domain_separation,
+                    }),
+                    _ => Err(InvalidDomainSeparation(domain_separation)),
+                }
+            }
+
+            /// The domain separation byte `D` of this instance.
+            pub fn domain_separation(&self) -> u8 {
+                self.domain_separation
+            }
+        }
+
+        impl HashMarker for $name {}
+
+        impl Update for $name {
+            fn update(&mut self, data: &[u8]) {
+                self.sponge.absorb(data);
+            }
+        }
+
+        #[cfg(feature = "std")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+        impl std::io::Write for $name {
+            #[inline]
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            #[inline]
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = $reader;
+
+            fn finalize_xof(mut self) -> Self::Reader {
+                self.finalize_xof_reset()
+            }
+        }
+
+        impl ExtendableOutputReset for $name {
+            fn finalize_xof_reset(&mut self) -> Self::Reader {
+                let mut state = mem::take(&mut self.sponge);
+                state.finalize(self.domain_separation);
+                $reader { state }
+            }
+        }
+
+        impl Reset for $name {
+            /// Reset the sponge, keeping the domain separation byte.
+            fn reset(&mut self) {
+                self.sponge = TurboShake::new();
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+        impl zeroize::ZeroizeOnDrop for $name {}
+
+        $(#[$reader_attr])*
+        #[derive(Clone, Debug)]
+        pub struct $reader {
+            /// Sponge state at the current position
+            state: TurboShake<$rate, ROUNDS>,
+        }
+
+        impl XofReader for $reader {
+            fn read(&mut self, output: &mut [u8]) {
+                self.state.squeeze(output);
+            }
+        }
+
+        #[cfg(feature = "std")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+        impl std::io::Read for $reader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.state.squeeze(buf);
+                Ok(buf.len())
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+        impl zeroize::ZeroizeOnDrop for $reader {}
+    };
+}
+
+turbo_shake_xof!(
+    /// The TurboSHAKE128 extendable-output function (XOF), with 128-bit
+    /// security.
+    ///
+    /// TurboSHAKE is the sponge on Keccak-p[1600, 12] that KangarooTwelve's
+    /// tree is built on, specified in RFC 9861. Its domain separation byte
+    /// `D` lets protocols derive independent functions from it.
+    ///
+    /// ```
+    /// use k12::{digest::{ExtendableOutput, Update, XofReader}, TurboShake128};
+    ///
+    /// let mut xof = TurboShake128::new(0x1F).unwrap();
+    /// xof.update(b"hello world");
+    /// let mut output = [0u8; 32];
+    /// xof.finalize_xof().read(&mut output);
+    /// ```
+    TurboShake128,
+    /// Extensible output reader of [`TurboShake128`].
+    TurboShake128Reader,
+    RATE
+);
+
+turbo_shake_xof!(
+    /// The TurboSHAKE256 extendable-output function (XOF), with 256-bit
+    /// security.
+    ///
+    /// The same as [`TurboShake128`] with a capacity of 512 bits, as used by
+    /// [`Kt256`](crate::Kt256).
+    ///
+    /// ```
+    /// use k12::{digest::{ExtendableOutput, Update, XofReader}, TurboShake256};
+    ///
+    /// let mut xof = TurboShake256::new(0x1F).unwrap();
+    /// xof.update(b"hello world");
+    /// let mut output = [0u8; 64];
+    /// xof.finalize_xof().read(&mut output);
+    /// ```
+    TurboShake256,
+    /// Extensible output reader of [`TurboShake256`].
+    TurboShake256Reader,
+    RATE_256
+);
@@ -0,0 +1,286 @@
+//! Keccak-p[1600, 12] on two interleaved states using SSE2, on four using
+//! AVX2, or on eight using AVX-512 with the `avx512` feature, selected at
+//! runtime.
+
+#![allow(unsafe_code)]
+
+use crate::{lanes::PI, lanes::RC, lanes::RHO, multi_buffer, CHAINING_VALUE_SIZE};
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+cpufeatures::new!(avx2_cpuid, "avx2");
+cpufeatures::new!(sse2_cpuid, "sse2");
+
+/// Whether the CPU supports AVX2, detected on first use.
+pub(crate) fn has_avx2() -> bool {
+    avx2_cpuid::get()
+}
+
+/// Hash 4 whole chunks as leaves in lockstep with AVX2, writing their
+/// chaining values to `cvs`.
+///
+/// # Panics
+///
+/// If the CPU does not support AVX2.
+pub(crate) fn hash_leaves_x4(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    assert!(has_avx2());
+    // SAFETY: AVX2 support was just checked
+    multi_buffer::hash_leaves_with(chunks, cvs, |lanes| unsafe { keccak_x4(lanes) });
+}
+
+/// Whether the CPU supports SSE2, detected on first use.
+pub(crate) fn has_sse2() -> bool {
+    sse2_cpuid::get()
+}
+
+/// Hash 2 whole chunks as leaves in lockstep with SSE2, writing their
+/// chaining values to `cvs`.
+///
+/// # Panics
+///
+/// If the CPU does not support SSE2.
+pub(crate) fn hash_leaves_x2(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    assert!(has_sse2());
+    // SAFETY: SSE2 support was just checked
+    multi_buffer::hash_leaves_with(chunks, cvs, |lanes| unsafe { keccak_x2(lanes) });
+}
+
+/// Whether the CPU supports AVX-512F, detected on first use.
+#[cfg(feature = "avx512")]
+pub(crate) fn has_avx512() -> bool {
+    std::is_x86_feature_detected!("avx512f")
+}
+
+/// Hash 8 whole chunks as leaves in lockstep with AVX-512, writing their
+/// chaining values to `cvs`.
+///
+/// # Panics
+///
+/// If the CPU does not support AVX-512F.
+#[cfg(feature = "avx512")]
+pub(crate) fn hash_leaves_x8(chunks: &[u8], cvs: &mut [[u8; CHAINING_VALUE_SIZE]]) {
+    assert!(has_avx512());
+    // SAFETY: AVX-512F support was just checked
+    multi_buffer::hash_leaves_with(chunks, cvs, |lanes| unsafe { keccak_x8(lanes) });
+}
+
+/// Define a Keccak-p[1600, 12] on as many interleaved states as the vector
+/// type `$v` has 64-bit lanes, from its intrinsics.
+macro_rules! keccak_x {
+    (
+        $name:ident, $feature:tt, $n:literal, $v:ty,
+        $zero:ident, $load:ident, $store:ident, $xor:ident, $andnot:ident, $set1:ident, $rotl:ident
+    ) => {
+        #[target_feature(enable = $feature)]
+        unsafe fn $name(lanes: &mut [[u64; $n]; 25]) {
+            let mut s = [$zero(); 25];
+            for (s, lane) in s.iter_mut().zip(lanes.iter()) {
+                *s = $load(lane.as_ptr() as *const $v);
+            }
+
+            let mut c = [$zero(); 5];
+            let (mut x, mut y): (usize, usize);
+
+            for &rc in RC.iter() {
+                // θ
+                FOR5!(x, 1, {
+                    c[x] = $xor(
+                        $xor($xor(s[x], s[x + 5]), $xor(s[x + 10], s[x + 15])),
+                        s[x + 20],
+                    );
+                });
+
+                FOR5!(x, 1, {
+                    let d = $xor(c[(x + 4) % 5], $rotl(c[(x + 1) % 5], 1));
+                    FOR5!(y, 5, {
+                        s[x + y] = $xor(s[x + y], d);
+                    });
+                });
+
+                // ρ and π
+                let mut a = s[1];
+                x = 0;
+                REPEAT24!(
+                    {
+                        c[0] = s[PI[x]];
+                        s[PI[x]] = $rotl(a, RHO[x]);
+                    },
+                    {
+                        a = c[0];
+                        x += 1;
+                    }
+                );
+
+                // χ
+                FOR5!(y, 5, {
+                    FOR5!(x, 1, {
+                        c[x] = s[x + y];
+                    });
+                    FOR5!(x, 1, {
+                        s[x + y] = $xor(c[x], $andnot(c[(x + 1) % 5], c[(x + 2) % 5]));
+                    });
+                });
+
+                // ι
+                s[0] = $xor(s[0], $set1(rc as i64));
+            }
+
+            for (s, lane) in s.iter().zip(lanes.iter_mut()) {
+                $store(lane.as_mut_ptr() as *mut $v, *s);
+            }
+        }
+    };
+}
+
+keccak_x!(
+    keccak_x4,
+    "avx2",
+    4,
+    __m256i,
+    _mm256_setzero_si256,
+    _mm256_loadu_si256,
+    _mm256_storeu_si256,
+    _mm256_xor_si256,
+    _mm256_andnot_si256,
+    _mm256_set1_epi64x,
+    rotl_avx2
+);
+keccak_x!(
+    keccak_x2,
+    "sse2",
+    2,
+    __m128i,
+    _mm_setzero_si128,
+    _mm_loadu_si128,
+    _mm_storeu_si128,
+    _mm_xor_si128,
+    _mm_andnot_si128,
+    _mm_set1_epi64x,
+    rotl_sse2
+);
+
+// Only logic and rotate instructions are used, which on Skylake-SP and
+// Cascade Lake still lower the clock of the core to the AVX-512 light
+// license for a few milliseconds, slowing down other code running on it.
+// That is why this is opt-in: it pays off hashing large inputs, but may not
+// when interleaved with short messages on a busy server. Ice Lake and later
+// cores barely downclock.
+#[cfg(feature = "avx512")]
+#[target_feature(enable = "avx512f")]
+unsafe fn keccak_x8(lanes: &mut [[u64; 8]; 25]) {
+    let mut s = [_mm512_setzero_si512(); 25];
+    for (s, lane) in s.iter_mut().zip(lanes.iter()) {
+        *s = _mm512_loadu_si512(lane.as_ptr().cast());
+    }
+
+    let mut c = [_mm512_setzero_si512(); 5];
+    let (mut x, mut y): (usize, usize);
+
+    for &rc in RC.iter() {
+        // θ, with 0x96 selecting the XOR of all three operands
+        FOR5!(x, 1, {
+            c[x] = _mm512_ternarylogic_epi64::<0x96>(
+                _mm512_ternarylogic_epi64::<0x96>(s[x], s[x + 5], s[x + 10]),
+                s[x + 15],
+                s[x + 20],
+            );
+        });
+
+        FOR5!(x, 1, {
+            let d = _mm512_xor_si512(c[(x + 4) % 5], _mm512_rol_epi64::<1>(c[(x + 1) % 5]));
+            FOR5!(y, 5, {
+                s[x + y] = _mm512_xor_si512(s[x + y], d);
+            });
+        });
+
+        // ρ and π
+        let mut a = s[1];
+        x = 0;
+        REPEAT24!(
+            {
+                c[0] = s[PI[x]];
+                s[PI[x]] = _mm512_rolv_epi64(a, _mm512_set1_epi64(i64::from(RHO[x])));
+            },
+            {
+                a = c[0];
+                x += 1;
+            }
+        );
+
+        // χ, with 0xD2 selecting a ^ (!b & c)
+        FOR5!(y, 5, {
+            FOR5!(x, 1, {
+                c[x] = s[x + y];
+            });
+            FOR5!(x, 1, {
+                s[x + y] = _mm512_ternarylogic_epi64::<0xD2>(c[x], c[(x + 1) % 5], c[(x + 2) % 5]);
+            });
+        });
+
+        // ι
+        s[0] = _mm512_xor_si512(s[0], _mm512_set1_epi64(rc as i64));
+    }
+
+    for (s, lane) in s.iter().zip(lanes.iter_mut()) {
+        _mm512_storeu_si512(lane.as_mut_ptr().cast(), *s);
+    }
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn rotl_avx2(a: __m256i, n: u32) -> __m256i {
+    let left = _mm_cvtsi32_si128(n as i32);
+    let right = _mm_cvtsi32_si128(64 - n as i32);
+    _mm256_or_si256(_mm256_sll_epi64(a, left), _mm256_srl_epi64(a, right))
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn rotl_sse2(a: __m128i, n: u32) -> __m128i {
+    let left = _mm_cvtsi32_si128(n as i32);
+    let right = _mm_cvtsi32_si128(64 - n as i32);
+    _mm_or_si128(_mm_sll_epi64(a, left), _mm_srl_epi64(a, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{backend::HashLeaves, leaf_chaining_value, CHAINING_VALUE_SIZE, CHUNK_SIZE};
+
+    fn check<const N: usize>(hash_leaves: HashLeaves) {
+        let mut input = [0u8; 8 * CHUNK_SIZE];
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = (i % 251) as u8;
+        }
+        let chunks = &input[..N * CHUNK_SIZE];
+        let mut cvs = [[0u8; CHAINING_VALUE_SIZE]; N];
+        hash_leaves(chunks, &mut cvs);
+        for (chunk, cv) in chunks.chunks_exact(CHUNK_SIZE).zip(&cvs) {
+            assert_eq!(*cv, leaf_chaining_value(chunk));
+        }
+    }
+
+    #[test]
+    fn sse2_matches_single_leaf() {
+        if super::has_sse2() {
+            check::<2>(super::hash_leaves_x2);
+        }
+    }
+
+    #[test]
+    fn avx2_matches_single_leaf() {
+        if super::has_avx2() {
+            check::<4>(super::hash_leaves_x4);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "avx512")]
+    fn avx512_matches_single_leaf() {
+        if super::has_avx512() {
+            check::<8>(super::hash_leaves_x8);
+        }
+    }
+}
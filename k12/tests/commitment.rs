@@ -0,0 +1,56 @@
+use hex_literal::hex;
+use k12::commitment::{self, Commitment, Opening};
+
+mod common;
+use common::ptn;
+
+#[test]
+#[rustfmt::skip]
+fn commitment_vectors() {
+    let opening = Opening::from_bytes([0; 32]);
+    assert_eq!(
+        opening.commitment(b"").as_bytes(),
+        &hex!("12ccae7ba635609ed4b8fb5b43dd191731bd306907f0ce653e1ef8118101e7f0")
+    );
+    assert_eq!(
+        opening.commitment(&ptn(17usize.pow(4))).as_bytes(),
+        &hex!("6d937d5cc289e591e700a6286332581f99d5bc01797378b58d269babc29a0872")
+    );
+}
+
+#[test]
+fn verify() {
+    let opening = Opening::from_bytes([0x42; 32]);
+    let commitment = opening.commitment(b"heads");
+    assert!(commitment::verify(&commitment, b"heads", &opening));
+    assert!(!commitment::verify(&commitment, b"tails", &opening));
+    assert!(!commitment::verify(
+        &commitment,
+        b"heads",
+        &Opening::from_bytes([0x24; 32])
+    ));
+
+    let received = Commitment::from_bytes(*commitment.as_bytes());
+    assert!(commitment::verify(&received, b"heads", &opening));
+}
+
+#[test]
+#[cfg(feature = "rand_core")]
+fn commit() {
+    use k12::{rand_core::SeedableRng, K12Rng};
+
+    let seed = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31,
+    ];
+    let (commitment, opening) = commitment::commit(b"hello world", &mut K12Rng::from_seed(seed));
+    assert_eq!(
+        opening.as_bytes(),
+        &hex!("2abff1023c02ff3b8c16217f2b5a7d675d7e8325963d4e5c7af43f41e2914408")
+    );
+    assert_eq!(
+        commitment.as_bytes(),
+        &hex!("878433af5dbe5397339dfdebba29913bbcb0a57af3d29d3856981f7c0624224c")
+    );
+    assert!(commitment::verify(&commitment, b"hello world", &opening));
+}
@@ -0,0 +1,24 @@
+//! Helpers shared by the integration tests.
+//!
+//! The test vectors of tests starting with a `// Source:` comment are
+//! taken from that specification. The others are regression values
+//! computed with this crate, and checked against an independent Python
+//! implementation of the reference code K12.py, extended to the rounds and
+//! rates of each variant and to the framing documented for each
+//! construction.
+
+#![allow(dead_code)]
+
+use k12::digest::{ExtendableOutput, Update};
+
+/// Output of `n` bytes of a fresh `H` over `data`.
+pub fn digest_and_box<H: Default + ExtendableOutput + Update>(data: &[u8], n: usize) -> Box<[u8]> {
+    let mut h = H::default();
+    h.update(data);
+    h.finalize_boxed(n)
+}
+
+/// Pattern string `ptn(len)` used by the test vectors.
+pub fn ptn(len: usize) -> Vec<u8> {
+    (0..len).map(|j| (j % 251) as u8).collect()
+}
@@ -0,0 +1,88 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, Update},
+    KangarooTwelve, KangarooTwelveGeneric,
+};
+
+mod common;
+use common::ptn;
+
+fn digest_and_box<const ROUNDS: usize>(data: &[u8], customization: &[u8]) -> Box<[u8]> {
+    let mut h = KangarooTwelveGeneric::<ROUNDS>::new_with_customization(customization);
+    h.update(data);
+    h.finalize_boxed(32)
+}
+
+#[test]
+fn twelve_rounds_are_kangaroo_twelve() {
+    let c = ptn(41);
+    for &len in &[0, 17, 8191, 8192, 8193, 17usize.pow(4)] {
+        let m = ptn(len);
+        let mut h = KangarooTwelve::new_with_customization(&c);
+        h.update(&m);
+        assert_eq!(
+            digest_and_box::<12>(&m, &c),
+            h.finalize_boxed(32),
+            "{}",
+            len
+        );
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn other_rounds() {
+    let (m, c) = (ptn(17usize.pow(4)), ptn(41usize.pow(2)));
+
+    assert_eq!(
+        digest_and_box::<1>(b"", b"")[..],
+        hex!("0887008000000080000000000008700000408003000000000007000000000000")[..]
+    );
+    assert_eq!(
+        digest_and_box::<1>(&m, b"")[..],
+        hex!("179fae2af51f2e2f0084de1aa414be617c74dcb8b23853265a431a08c2923493")[..]
+    );
+    assert_eq!(
+        digest_and_box::<1>(&[0xFF; 3], &c)[..],
+        hex!("fc2da9b7431360c6b749f03630d846c30f1fe59e082983a5973b49f743bd2a37")[..]
+    );
+
+    assert_eq!(
+        digest_and_box::<6>(b"", b"")[..],
+        hex!("ddf2c2a8255ca28bb03e0eae2f15ce4804f66cacbee11f4bfb487a5360c342a0")[..]
+    );
+    assert_eq!(
+        digest_and_box::<6>(&m, b"")[..],
+        hex!("32e23882ed1d3049bda166f274aa6d3761a385c3ab049158ecf3c62909bd1d5e")[..]
+    );
+    assert_eq!(
+        digest_and_box::<6>(&[0xFF; 3], &c)[..],
+        hex!("a9bbecddaaaf7aa322c6acffcd8970eefeb4561dbd9243006341347a811882b6")[..]
+    );
+
+    assert_eq!(
+        digest_and_box::<14>(b"", b"")[..],
+        hex!("fd22bbcbae7c24f3817fc8b85c10ac839f2b8618e606af6f8963f4344e6852f4")[..]
+    );
+    assert_eq!(
+        digest_and_box::<14>(&m, b"")[..],
+        hex!("f49ba74d22c8ac9412fcc965af0f79b05d84d87df6ad2e9d0bf6c67f4d0a25e5")[..]
+    );
+    assert_eq!(
+        digest_and_box::<14>(&[0xFF; 3], &c)[..],
+        hex!("99446b5a1cbd10c8d47b52936f78aae1a18e9278f3fa58223321ef1f2ffe6918")[..]
+    );
+
+    assert_eq!(
+        digest_and_box::<24>(b"", b"")[..],
+        hex!("d9e247ab6e375eda0493ea9baa528c12f44091be293b4b492d23211b1e0b8bf8")[..]
+    );
+    assert_eq!(
+        digest_and_box::<24>(&m, b"")[..],
+        hex!("8fe315137d58897b8052e95c285aff9676156fe69326e5689e4c77789b7087b2")[..]
+    );
+    assert_eq!(
+        digest_and_box::<24>(&[0xFF; 3], &c)[..],
+        hex!("fd8314215d0b173c893de4b0a14f27d022c0af9f09cca2b1417468a037b0696a")[..]
+    );
+}
@@ -0,0 +1,88 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, Mac, Update},
+    HopMac, KangarooTwelve,
+};
+
+mod common;
+use common::ptn;
+
+fn hop_mac(key: &[u8], data: &[u8], customization: &[u8]) -> [u8; 32] {
+    let mut mac = HopMac::new_with_customization(key, customization);
+    Mac::update(&mut mac, data);
+    mac.finalize().into_bytes().into()
+}
+
+#[test]
+#[rustfmt::skip]
+fn vectors() {
+    let key = ptn(32);
+    assert_eq!(
+        hop_mac(&key, b"", b""),
+        hex!("d9b9af15721ed3b1bf370a504e9e506f0fc0fe944c8f10537e292a76ad959329")
+    );
+    assert_eq!(
+        hop_mac(&key, &ptn(17usize.pow(3)), b""),
+        hex!("7ac99419b2cd30a4c47cf1bbd4b1f9a5fa1a1d01add51c5f789dc04db1226f40")
+    );
+    assert_eq!(
+        hop_mac(&key, &ptn(17usize.pow(4)), &ptn(41)),
+        hex!("24f8e4aecaebde422e60988c1da6d47cf1c115bcba5312ff22832187299284a4")
+    );
+    assert_eq!(
+        hop_mac(&ptn(9000), &ptn(17usize.pow(3)), b""),
+        hex!("b26bd8e043ce72b27aca77485a8db01bfdcbdd8e43cac5d0965405585712bf10")
+    );
+    assert_eq!(
+        hop_mac(&ptn(16), &ptn(17usize.pow(3)), b""),
+        hex!("16249cb02415be5d171fb7c3d8ccb5e0ed2a99873e7d5b6becc22ed4532096d6")
+    );
+}
+
+#[test]
+fn nested_kangaroo_twelve() {
+    let (key, m, c) = (ptn(100), ptn(20000), ptn(41));
+    let mut inner = [0u8; 32];
+    KangarooTwelve::new_with_customization(&c)
+        .chain(&m)
+        .finalize_xof_into(&mut inner);
+    let mut expected = [0u8; 32];
+    KangarooTwelve::new_with_customization(&inner)
+        .chain(&key)
+        .finalize_xof_into(&mut expected);
+    assert_eq!(hop_mac(&key, &m, &c), expected);
+}
+
+#[test]
+fn verify_and_reset() {
+    let key = ptn(32);
+    let tag = hop_mac(&key, b"message", b"");
+
+    let mut mac = <HopMac as Mac>::new_from_slice(&key).unwrap();
+    Mac::update(&mut mac, b"mess");
+    Mac::update(&mut mac, b"age");
+    assert_eq!(mac.finalize_reset().into_bytes()[..], tag[..]);
+
+    Mac::update(&mut mac, b"message");
+    assert!(mac.clone().verify_slice(&tag).is_ok());
+    let mut wrong = tag;
+    wrong[31] ^= 1;
+    assert!(mac.verify_slice(&wrong).is_err());
+
+    let mut mac = <HopMac as Mac>::new_from_slice(&ptn(31)).unwrap();
+    Mac::update(&mut mac, b"message");
+    assert!(mac.verify_slice(&tag).is_err());
+}
+
+#[test]
+fn short_keys() {
+    assert!(<HopMac as Mac>::new_from_slice(b"").is_err());
+    assert!(<HopMac as Mac>::new_from_slice(&ptn(15)).is_err());
+    assert!(<HopMac as Mac>::new_from_slice(&ptn(16)).is_ok());
+}
+
+#[test]
+#[should_panic]
+fn short_key_with_customization() {
+    HopMac::new_with_customization(&ptn(15), b"");
+}
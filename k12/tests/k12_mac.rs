@@ -0,0 +1,66 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, Mac, Update},
+    K12Mac, KangarooTwelve,
+};
+
+mod common;
+use common::ptn;
+
+fn k12_mac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = <K12Mac as Mac>::new_from_slice(key).unwrap();
+    Mac::update(&mut mac, data);
+    mac.finalize().into_bytes().into()
+}
+
+#[test]
+#[rustfmt::skip]
+fn vectors() {
+    let key = ptn(32);
+    assert_eq!(
+        k12_mac(&key, b""),
+        hex!("fbcac32b3f8fa2d720e1e4d889500e276a10006102bcbc23f6d052dc17b6dadc")
+    );
+    assert_eq!(
+        k12_mac(&key, &ptn(17usize.pow(3))),
+        hex!("ebf8ce568227ee6ee5159495b9956fd359a46c33f3fdb3261673907b17675383")
+    );
+    assert_eq!(
+        k12_mac(&key, &ptn(17usize.pow(5))),
+        hex!("18f5b5357ccacaf0a02ef905f56c307b921eaddd4629757b10f6bfbb7ec2ecd9")
+    );
+}
+
+#[test]
+fn key_as_customization() {
+    let (key, m) = (ptn(32), ptn(20000));
+    let mut expected = [0u8; 32];
+    KangarooTwelve::new_with_customization(&key)
+        .chain(&m)
+        .finalize_xof_into(&mut expected);
+    assert_eq!(k12_mac(&key, &m), expected);
+}
+
+#[test]
+fn key_length() {
+    for &len in &[0, 16, 31, 33, 64] {
+        assert!(<K12Mac as Mac>::new_from_slice(&ptn(len)).is_err());
+    }
+}
+
+#[test]
+fn verify_and_reset() {
+    let key = ptn(32);
+    let tag = k12_mac(&key, b"message");
+
+    let mut mac = <K12Mac as Mac>::new_from_slice(&key).unwrap();
+    Mac::update(&mut mac, b"mess");
+    Mac::update(&mut mac, b"age");
+    assert_eq!(mac.finalize_reset().into_bytes()[..], tag[..]);
+
+    Mac::update(&mut mac, b"message");
+    assert!(mac.clone().verify_slice(&tag).is_ok());
+    let mut wrong = tag;
+    wrong[0] ^= 0x80;
+    assert!(mac.verify_slice(&wrong).is_err());
+}
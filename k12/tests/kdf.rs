@@ -0,0 +1,65 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, Update},
+    kdf, KangarooTwelve,
+};
+
+mod common;
+use common::ptn;
+
+#[test]
+#[rustfmt::skip]
+fn vectors() {
+    let mut okm = [0u8; 32];
+    kdf(b"", b"", &mut okm);
+    assert_eq!(
+        okm,
+        hex!("0a53fd4cc8f5d0f57cc99869a87308b7a403243c198b3a143ad89d2df8119ffe")
+    );
+
+    let mut okm = [0u8; 64];
+    kdf(b"example.com 2026 session keys", &ptn(32), &mut okm);
+    assert_eq!(
+        okm[..],
+        hex!("
+            5210075201668bcfa5e65d7baf5d1da8c646dd73eb513da4af53059fd6ef9247
+            f75ef65e3893f1c7ec0940c1512e471cf8fa6124333093825876a96dc22ec556
+        ")[..]
+    );
+
+    let mut okm = [0u8; 32];
+    kdf(&ptn(41usize.pow(2)), &ptn(17usize.pow(4)), &mut okm);
+    assert_eq!(
+        okm,
+        hex!("9d6af1dd2863cf221bb60f6f851ff917c0ba0b9a30774d2f45a5d890de3ffd09")
+    );
+}
+
+#[test]
+fn labeled_customization() {
+    let (context, ikm) = (ptn(9000), ptn(9000));
+    let mut customization = b"KangarooTwelve KDF".to_vec();
+    customization.extend_from_slice(&context);
+    let mut expected = [0u8; 100];
+    KangarooTwelve::new_with_customization(&customization)
+        .chain(&ikm)
+        .finalize_xof_into(&mut expected);
+
+    let mut okm = [0u8; 100];
+    kdf(&context, &ikm, &mut okm);
+    assert_eq!(okm[..], expected[..]);
+}
+
+#[test]
+fn context_and_ikm_are_separated() {
+    let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+    kdf(b"ab", b"c", &mut a);
+    kdf(b"a", b"bc", &mut b);
+    assert_ne!(a, b);
+
+    let mut plain = [0u8; 32];
+    KangarooTwelve::new_with_customization(b"ab")
+        .chain(b"c")
+        .finalize_xof_into(&mut plain);
+    assert_ne!(a, plain);
+}
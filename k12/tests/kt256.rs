@@ -0,0 +1,154 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, ExtendableOutputReset, Update, XofReader},
+    Kt256,
+};
+
+mod common;
+use common::{digest_and_box, ptn};
+
+#[test]
+#[rustfmt::skip]
+fn empty() {
+    // Source: RFC 9861, section 5
+    assert_eq!(
+        digest_and_box::<Kt256>(b"", 64)[..],
+        hex!("
+            b23d2e9cea9f4904e02bec06817fc10ce38ce8e93ef4c89e6537076af8646404
+            e3e8b68107b8833a5d30490aa33482353fd4adc7148ecb782855003aaebde4a9
+        ")[..]
+    );
+
+    assert_eq!(
+        digest_and_box::<Kt256>(b"", 128)[..],
+        hex!("
+            b23d2e9cea9f4904e02bec06817fc10ce38ce8e93ef4c89e6537076af8646404
+            e3e8b68107b8833a5d30490aa33482353fd4adc7148ecb782855003aaebde4a9
+            b0925319d8ea1e121a609821ec19efea89e6d08daee1662b69c840289f188ba8
+            60f55760b61f82114c030c97e5178449608ccd2cd2d919fc7829ff69931ac4d0
+        ")[..]
+    );
+
+    assert_eq!(
+        digest_and_box::<Kt256>(b"", 10064)[10000..],
+        hex!("
+            ad4a1d718cf950506709a4c33396139b4449041fc79a05d68da35f1e453522e0
+            56c64fe94958e7085f2964888259b9932752f3ccd855288efee5fcbb8b563069
+        ")[..]
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn pat_m() {
+    let expected = [
+        hex!("
+            0d005a194085360217128cf17f91e1f71314efa5564539d444912e3437efa17f
+            82db6f6ffe76e781eaa068bce01f2bbf81eacb983d7230f2fb02834a21b1ddd0
+        "),
+        hex!("
+            1ba3c02b1fc514474f06c8979978a9056c8483f4a1b63d0dccefe3a28a2f323e
+            1cdcca40ebf006ac76ef0397152346837b1277d3e7faa9c9653b19075098527b
+        "),
+        hex!("
+            de8ccbc63e0f133ebb4416814d4c66f691bbf8b6a61ec0a7700f836b086cb029
+            d54f12ac7159472c72db118c35b4e6aa213c6562caaa9dcc518959e69b10f3ba
+        "),
+        hex!("
+            647efb49fe9d717500171b41e7f11bd491544443209997ce1c2530d15eb1ffbb
+            598935ef954528ffc152b1e4d731ee2683680674365cd191d562bae753b84aa5
+        "),
+        hex!("
+            b06275d284cd1cf205bcbe57dccd3ec1ff6686e3ed15776383e1f2fa3c6ac8f0
+            8bf8a162829db1a44b2a43ff83dd89c3cf1ceb61ede659766d5ccf817a62ba8d
+        "),
+        hex!("
+            9473831d76a4c7bf77ace45b59f1458b1673d64bcd877a7c66b2664aa6dd149e
+            60eab71b5c2bab858c074ded81ddce2b4022b5215935c0d4d19bf511aeeb0772
+        "),
+    ];
+    for (i, expected) in expected.iter().enumerate() {
+        let m = ptn(17usize.pow(i as u32));
+        assert_eq!(digest_and_box::<Kt256>(&m, 64)[..], expected[..]);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn pat_c() {
+    let expected = [
+        hex!("
+            9280f5cc39b54a5a594ec63de0bb99371e4609d44bf845c2f5b8c316d72b1598
+            11f748f23e3fabbe5c3226ec96c62186df2d33e9df74c5069ceecbb4dd10eff6
+        "),
+        hex!("
+            47ef96dd616f200937aa7847e34ec2feae8087e3761dc0f8c1a154f51dc9ccf8
+            45d7adbce57ff64b639722c6a1672e3bf5372d87e00aff89be97240756998853
+        "),
+        hex!("
+            3b48667a5051c5966c53c5d42b95de451e05584e7806e2fb765eda959074172c
+            b438a9e91dde337c98e9c41bed94c4e0aef431d0b64ef2324f7932caa6f54969
+        "),
+        hex!("
+            e0911cc00025e1540831e266d94add9b98712142b80d2629e643aac4efaf5a3a
+            30a88cbf4ac2a91a2432743054fbcc9897670e86ba8cec2fc2ace9c966369724
+        "),
+    ];
+    for (i, expected) in expected.iter().enumerate() {
+        let m = vec![0xFF; 2usize.pow(i as u32) - 1];
+        let c = ptn(41usize.pow(i as u32));
+        let mut h = Kt256::new_with_customization(&c);
+        h.update(&m);
+        assert_eq!(h.finalize_boxed(64)[..], expected[..]);
+    }
+}
+
+#[test]
+fn pat_m_incremental() {
+    let m = ptn(17usize.pow(5));
+    let expected = digest_and_box::<Kt256>(&m, 64);
+    for &piece in &[1, 7, 136, 1000, 8191, 8192, 8193] {
+        let mut h = Kt256::new();
+        for chunk in m.chunks(piece) {
+            h.update(chunk);
+        }
+        assert_eq!(h.message_len(), m.len() as u64);
+        assert_eq!(h.finalize_boxed(64), expected, "{}", piece);
+    }
+}
+
+#[test]
+fn reset_and_reads() {
+    let c = ptn(100);
+    let mut h = Kt256::new_with_customization(&c).chain_update(ptn(9000));
+    let first = h.finalize_boxed_reset(64);
+    h.update(&ptn(9000));
+    let mut reader = h.finalize_xof();
+    let mut output = [0u8; 64];
+    for piece in output.chunks_mut(9) {
+        reader.read(piece);
+    }
+    assert_eq!(output[..], first[..]);
+}
+
+#[test]
+#[rustfmt::skip]
+fn chunk_boundaries() {
+    let expected = [
+        (8191, hex!("
+            3081434d93a4108d8d8a3305b89682cebedc7ca4ea8a3ce869fbb73cbe4a58ee
+            f6f24de38ffc170514c70e7ab2d01f03812616e863d769afb3753193ba045b20
+        ")),
+        (8192, hex!("
+            c6ee8e2ad3200c018ac87aaa031cdac22121b412d07dc6e0dccbb53423747e9a
+            1c18834d99df596cf0cf4b8dfafb7bf02d139d0c9035725adc1a01b7230a41fa
+        ")),
+        (8193, hex!("
+            65ff03335900e5197acbd5f41b797f0e7e36ad4ff7d89c09fa6f28ae58d1e8bc
+            2df1779b86f988c3b13690172914ea172423b23ef4057255bb0836ab3a99836e
+        ")),
+    ];
+    for (len, expected) in expected.iter() {
+        assert_eq!(digest_and_box::<Kt256>(&ptn(*len), 64)[..], expected[..], "{}", len);
+    }
+}
@@ -0,0 +1,153 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, ExtendableOutputReset, Update, XofReader},
+    MarsupilamiFourteen,
+};
+
+mod common;
+use common::{digest_and_box, ptn};
+
+#[test]
+#[rustfmt::skip]
+fn empty() {
+    assert_eq!(
+        digest_and_box::<MarsupilamiFourteen>(b"", 64)[..],
+        hex!("
+            6f66ef1474eb53807aa329257c768bb88893d9f086e51da2f5c80d17ca0fc57d
+            5a24fac879014f8b30a3fdf5ac56ebafa219eb891d4bbbab7e1df3b27205b459
+        ")[..]
+    );
+
+    assert_eq!(
+        digest_and_box::<MarsupilamiFourteen>(b"", 128)[..],
+        hex!("
+            6f66ef1474eb53807aa329257c768bb88893d9f086e51da2f5c80d17ca0fc57d
+            5a24fac879014f8b30a3fdf5ac56ebafa219eb891d4bbbab7e1df3b27205b459
+            b9a1934666bb2793491e1767d2871a2d9fc5a5017ad3f598fbb651eab98849ec
+            d368f9795ff80685d2bb6ee114b1666fd02667293d563d1bafcf59514bf2ae7b
+        ")[..]
+    );
+
+    assert_eq!(
+        digest_and_box::<MarsupilamiFourteen>(b"", 10064)[10000..],
+        hex!("
+            c09322de1513d0cd604728f36d11adff58b93f776381095a071921eafb30e1e3
+            a987ea7bb413f5f2bae04089a866b479d2893a11b329a1657fbe3caab8077868
+        ")[..]
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn pat_m() {
+    let expected = [
+        hex!("
+            cc05ebc928156c7a03540085355c47c6aea1d07dc811cdded0e4c367f8d99368
+            a531825d996413a9bc0e1e572ff5df4f98ca65f4fb4900ee2355f59599e2f648
+        "),
+        hex!("
+            aa764fd8b38f19976a305cb007f19384b210a5c7b0fc4499d6f83c6227bff850
+            270b880cff3f17325b843e972ae0b99a25fa0e0050cc748f37c4cfc2592fd172
+        "),
+        hex!("
+            f18a6e250b1cc83dea89ffbb4de56a8e70041c71fc5b17a2aaab05c606aa6bf2
+            7c3955c946e8e215f0b1e2c93cb9e7a736c339c06f34e587df3bcc5847cf25f6
+        "),
+        hex!("
+            0ac89b11a06f46b2f6feeff046c97e90dc02910ae509b8739cfea5df1df90b82
+            895a5fad67ad2fa41259090756c0d988440fa3267a48380ada5df9c7f0290757
+        "),
+        hex!("
+            35af0a5fc6c4d111fbc68f879d05506aafd300b5ab136986d7aed8a9f1be331e
+            8664381864672e81ba32d828b2c05192a5886846f6c7570e7ebaeb97b59bd73e
+        "),
+        hex!("
+            0c982c5d5334e27cc6591cda308dfa6b4fdd736aadbe64536bdef83c1d496ba0
+            77c800d2fdfd564a8b8af328ec47731c5b82fc7f92fa5076f607e144b52cdb4e
+        "),
+    ];
+    for (i, expected) in expected.iter().enumerate() {
+        let m = ptn(17usize.pow(i as u32));
+        assert_eq!(digest_and_box::<MarsupilamiFourteen>(&m, 64)[..], expected[..]);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn pat_c() {
+    let expected = [
+        hex!("
+            e6c23ceeab2089d14dc3b088fdfe6d4418bf8a6f330fb3edcc300cd81e1bef2f
+            0cab479b196e53be8fa287854d484fdfd084af3ae1ffac9b04c2e9ea2b5a1c7b
+        "),
+        hex!("
+            2bab75b31b8c3049abeb7674774771b64f59225be20e930ebdbf8e37c24fad69
+            bef47a412db62094d5cc95de8e4fc2c0ae65fd0f4d03bb56e6292be084fcc8e3
+        "),
+        hex!("
+            732a60c308bebf5f7b3d3e8f0d26e324c04bab4197ca0a608b0befaa25ea5976
+            0718509c01fe503de2b970963f31e359e31f6ad5f6a591e83bc641d4cd6411dd
+        "),
+        hex!("
+            61583cdfaa64ab60e77b8c8bdd0ad088f9d760b2944f7d64c5dd81ce7e92d96b
+            ff67843a1eed51f301db51ff54fdcd4462fd051425d4c2edba74ac2b1532ec14
+        "),
+    ];
+    for (i, expected) in expected.iter().enumerate() {
+        let m = vec![0xFF; 2usize.pow(i as u32) - 1];
+        let c = ptn(41usize.pow(i as u32));
+        let mut h = MarsupilamiFourteen::new_with_customization(&c);
+        h.update(&m);
+        assert_eq!(h.finalize_boxed(64)[..], expected[..]);
+    }
+}
+
+#[test]
+fn pat_m_incremental() {
+    let m = ptn(17usize.pow(5));
+    let expected = digest_and_box::<MarsupilamiFourteen>(&m, 64);
+    for &piece in &[1, 7, 136, 1000, 8191, 8192, 8193] {
+        let mut h = MarsupilamiFourteen::new();
+        for chunk in m.chunks(piece) {
+            h.update(chunk);
+        }
+        assert_eq!(h.message_len(), m.len() as u64);
+        assert_eq!(h.finalize_boxed(64), expected, "{}", piece);
+    }
+}
+
+#[test]
+fn reset_and_reads() {
+    let c = ptn(100);
+    let mut h = MarsupilamiFourteen::new_with_customization(&c).chain_update(ptn(9000));
+    let first = h.finalize_boxed_reset(64);
+    h.update(&ptn(9000));
+    let mut reader = h.finalize_xof();
+    let mut output = [0u8; 64];
+    for piece in output.chunks_mut(9) {
+        reader.read(piece);
+    }
+    assert_eq!(output[..], first[..]);
+}
+
+#[test]
+#[rustfmt::skip]
+fn chunk_boundaries() {
+    let expected = [
+        (8191, hex!("
+            8884e4ea956aba88d03cc52e4ccbe236543a494d850bc8c663ed1606fef9ab60
+            8d5f223ecd73ea2a832a3f717eb18218baf5cacd214d2aff41c4e9f82136c13d
+        ")),
+        (8192, hex!("
+            56926c1964f5f1051da69d7d550b7377817cb084527efaedddfc49a07b829bd0
+            2ab73cd5dff77a6e8bfb30eb627674273dbb7530b688c4e9e03317e516f098a5
+        ")),
+        (8193, hex!("
+            6a923da37d86c121ab84e6525c89204a59352f74080b0dd9ee2d59c580a26004
+            1b1dcc9f0882fdf109f5c69d2b20207ec39dc9a3c2e9938acbcdc02fd0f71729
+        ")),
+    ];
+    for (len, expected) in expected.iter() {
+        assert_eq!(digest_and_box::<MarsupilamiFourteen>(&ptn(*len), 64)[..], expected[..], "{}", len);
+    }
+}
@@ -0,0 +1,59 @@
+//! Checks that hashing runs in constant memory.
+//!
+//! Kept in a separate test binary because it installs a counting global
+//! allocator.
+
+use k12::{
+    digest::{ExtendableOutput, Update},
+    KangarooTwelve, Reader,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    mem,
+};
+
+struct CountingAllocator;
+
+// Only count allocations made by the test thread itself, not by the test
+// harness running alongside it. (`const` initializers need a newer MSRV.)
+thread_local! {
+    #[allow(clippy::missing_const_for_thread_local)]
+    static ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+fn constant_memory() {
+    assert!(mem::size_of::<KangarooTwelve<'_>>() < 16 * 1024);
+    assert!(mem::size_of::<Reader>() < 16 * 1024);
+
+    let block = [0x5Au8; 1000];
+    let mut h = KangarooTwelve::new_with_customization(b"memory");
+
+    let before = allocations();
+    // 64 MB of input spanning several thousand chunks
+    for _ in 0..64_000 {
+        h.update(&block);
+    }
+    assert_eq!(allocations(), before);
+
+    h.finalize_boxed(32);
+}
@@ -1,27 +1,23 @@
-use core::iter;
 use hex_literal::hex;
 use k12::{
-    digest::{ExtendableOutput, Update},
+    digest::{ExtendableOutput, ExtendableOutputReset, Reset, Update, XofReader},
     KangarooTwelve,
 };
 
-fn digest_and_box(data: &[u8], n: usize) -> Box<[u8]> {
-    let mut h = KangarooTwelve::new();
-    h.update(data);
-    h.finalize_boxed(n)
-}
+mod common;
+use common::{digest_and_box, ptn};
 
 #[test]
 #[rustfmt::skip]
 fn empty() {
-    // Source: reference paper
+    // Source: RFC 9861, section 5
     assert_eq!(
-        digest_and_box(b"", 32)[..],
+        digest_and_box::<KangarooTwelve>(b"", 32)[..],
         hex!("1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5")[..]
     );
 
     assert_eq!(
-        digest_and_box(b"", 64)[..],
+        digest_and_box::<KangarooTwelve>(b"", 64)[..],
         hex!("
             1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5
             4269c056b8c82e48276038b6d292966cc07a3d4645272e31ff38508139eb0a71
@@ -29,13 +25,14 @@ fn empty() {
     );
 
     assert_eq!(
-        digest_and_box(b"", 10032)[10000..],
+        digest_and_box::<KangarooTwelve>(b"", 10032)[10000..],
         hex!("e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d")[..]
     );
 }
 
 #[test]
 fn pat_m() {
+    // Source: RFC 9861, section 5
     let expected = [
         hex!("2bda92450e8b147f8a7cb629e784a058efca7cf7d8218e02d345dfaa65244a1f"),
         hex!("6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888"),
@@ -46,17 +43,32 @@ fn pat_m() {
         hex!("3c390782a8a4e89fa6367f72feaaf13255c8d95878481d3cd8ce85f58e880af8"),
     ];
     for i in 0..5
-    /*NOTE: can be up to 7 but is slow*/
+    /*NOTE: can be up to 7 but is slow, see `pat_m_large`*/
     {
         let len = 17usize.pow(i);
         let m: Vec<u8> = (0..len).map(|j| (j % 251) as u8).collect();
-        let result = digest_and_box(&m, 32);
+        let result = digest_and_box::<KangarooTwelve>(&m, 32);
         assert_eq!(result[..], expected[i as usize][..]);
     }
 }
 
+#[test]
+#[ignore = "hashes 24 MiB"]
+fn pat_m_large() {
+    // Source: RFC 9861, section 5
+    let expected = [
+        hex!("844d610933b1b9963cbdeb5ae3b6b05cc7cbd67ceedf883eb678a0a8e0371682"),
+        hex!("3c390782a8a4e89fa6367f72feaaf13255c8d95878481d3cd8ce85f58e880af8"),
+    ];
+    for (i, expected) in (5..7).zip(&expected) {
+        let result = digest_and_box::<KangarooTwelve>(&ptn(17usize.pow(i)), 32);
+        assert_eq!(result[..], expected[..]);
+    }
+}
+
 #[test]
 fn pat_c() {
+    // Source: RFC 9861, section 5
     let expected = [
         hex!("fab658db63e94a246188bf7af69a133045f46ee984c56e3c3328caaf1aa1a583"),
         hex!("d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4"),
@@ -64,12 +76,1178 @@ fn pat_c() {
         hex!("75d2f86a2e644566726b4fbcfc5657b9dbcf070c7b0dca06450ab291d7443bcf"),
     ];
     for i in 0..4 {
-        let m: Vec<u8> = iter::repeat(0xFF).take(2usize.pow(i) - 1).collect();
+        let m = vec![0xFF; 2usize.pow(i) - 1];
         let len = 41usize.pow(i);
         let c: Vec<u8> = (0..len).map(|j| (j % 251) as u8).collect();
-        let mut h = KangarooTwelve::new_with_customization(c);
+        let mut h = KangarooTwelve::new_with_customization(&c);
         h.update(&m);
         let result = h.finalize_boxed(32);
         assert_eq!(result[..], expected[i as usize][..]);
     }
 }
+
+#[test]
+fn pat_m_incremental() {
+    let expected = hex!("844d610933b1b9963cbdeb5ae3b6b05cc7cbd67ceedf883eb678a0a8e0371682");
+    let m: Vec<u8> = (0..17usize.pow(5)).map(|j| (j % 251) as u8).collect();
+
+    for &piece in &[1, 7, 168, 1000, 8191, 8192, 8193] {
+        let mut h = KangarooTwelve::new();
+        for chunk in m.chunks(piece) {
+            h.update(chunk);
+        }
+        assert_eq!(h.finalize_boxed(32)[..], expected[..]);
+    }
+}
+
+#[test]
+fn chunk_ends() {
+    // Source: RFC 9861, section 5
+    let vectors = [
+        (
+            8191,
+            0,
+            hex!("1b577636f723643e990cc7d6a659837436fd6a103626600eb8301cd1dbe553d6"),
+        ),
+        (
+            8192,
+            0,
+            hex!("48f256f6772f9edfb6a8b661ec92dc93b95ebd05a08a17b39ae3490870c926c3"),
+        ),
+        (
+            8192,
+            8189,
+            hex!("3ed12f70fb05ddb58689510ab3e4d23c6c6033849aa01e1d8c220a297fedcd0b"),
+        ),
+        (
+            8192,
+            8190,
+            hex!("6a7c1b6a5cd0d8c9ca943a4a216cc64604559a2ea45f78570a15253d67ba00ae"),
+        ),
+    ];
+
+    for (m_len, c_len, expected) in vectors.iter() {
+        let c = ptn(*c_len);
+        let mut h = KangarooTwelve::new_with_customization(&c);
+        h.update(&ptn(*m_len));
+        assert_eq!(h.finalize_boxed(32)[..], expected[..]);
+    }
+}
+
+#[test]
+fn shared_prefix() {
+    let m = ptn(17usize.pow(4));
+    for &split in &[0, 1, 8191, 8192, 8193, 50_000] {
+        let mut prefix = KangarooTwelve::new();
+        prefix.update(&m[..split]);
+
+        let mut h = prefix.clone();
+        h.update(&m[split..]);
+        prefix.update(b"unrelated suffix");
+
+        assert_eq!(
+            h.finalize_boxed(32)[..],
+            hex!("8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe")[..]
+        );
+    }
+}
+
+#[test]
+fn reset_customization() {
+    let expected = hex!("d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4");
+    let c = ptn(41);
+
+    let mut h = KangarooTwelve::new_with_customization(&c);
+    h.update(b"\xFF");
+    assert_eq!(h.finalize_boxed_reset(32)[..], expected[..]);
+
+    h.update(b"garbage");
+    h.reset();
+    h.update(b"\xFF");
+    assert_eq!(h.finalize_boxed_reset(32)[..], expected[..]);
+
+    h.update(b"garbage");
+    h.reset_with_customization(b"");
+    assert_eq!(
+        h.finalize_boxed(32)[..],
+        hex!("1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5")[..]
+    );
+}
+
+#[test]
+fn const_constructor() {
+    const CUSTOMIZATION: [u8; 41] = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40,
+    ];
+    const HASHER: KangarooTwelve<'static> = KangarooTwelve::new_with_customization(&CUSTOMIZATION);
+
+    let mut h = HASHER;
+    h.update(b"\xFF");
+    assert_eq!(
+        h.finalize_boxed(32)[..],
+        hex!("d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4")[..]
+    );
+}
+
+#[test]
+fn introspection() {
+    let mut h = KangarooTwelve::new();
+    assert_eq!(
+        (h.message_len(), h.chunks_processed(), h.buffered_len()),
+        (0, 0, 0)
+    );
+
+    h.update(&[0; 8192]);
+    assert_eq!(
+        (h.message_len(), h.chunks_processed(), h.buffered_len()),
+        (8192, 0, 0)
+    );
+
+    h.update(&[0; 8193]);
+    assert_eq!(
+        (h.message_len(), h.chunks_processed(), h.buffered_len()),
+        (16385, 1, 1)
+    );
+}
+
+#[test]
+fn update_slices() {
+    let m = ptn(17usize.pow(4));
+    let (a, rest) = m.split_at(1000);
+    let (b, c) = rest.split_at(20_000);
+    let expected = hex!("8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe");
+
+    let mut h = KangarooTwelve::new();
+    h.update_slices(&[a, b, c]);
+    assert_eq!(h.finalize_boxed(32)[..], expected[..]);
+
+    let mut h = KangarooTwelve::new();
+    h.update_iter(m.chunks(3000).map(|chunk| chunk.to_vec()));
+    assert_eq!(h.finalize_boxed(32)[..], expected[..]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn update_vectored() {
+    use std::io::IoSlice;
+
+    let m = ptn(17usize.pow(4));
+    let (a, rest) = m.split_at(1000);
+    let (b, c) = rest.split_at(20_000);
+
+    let mut h = KangarooTwelve::new();
+    h.update_vectored(&[IoSlice::new(a), IoSlice::new(b), IoSlice::new(c)]);
+    assert_eq!(
+        h.finalize_boxed(32)[..],
+        hex!("8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe")[..]
+    );
+}
+
+#[test]
+fn finalize_xof_ref() {
+    let m = ptn(17usize.pow(4));
+    let mut h = KangarooTwelve::new();
+    let mut output = [0u8; 32];
+
+    h.update(&m[..17usize.pow(3)]);
+    h.finalize_xof_ref().read(&mut output);
+    assert_eq!(
+        output,
+        hex!("cb552e2ec77d9910701d578b457ddf772c12e322e4ee7fe417f92c758f0d59d0")
+    );
+
+    h.update(&m[17usize.pow(3)..]);
+    h.finalize_xof_ref().read(&mut output);
+    assert_eq!(
+        output,
+        hex!("8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe")
+    );
+}
+
+#[test]
+fn extend() {
+    let m = ptn(17usize.pow(4));
+    let expected = hex!("8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe");
+
+    let mut h = KangarooTwelve::new();
+    h.extend(m[..1000].iter());
+    h.extend(m[1000..].iter().copied());
+    assert_eq!(h.finalize_boxed(32)[..], expected[..]);
+
+    let h: KangarooTwelve<'_> = (0..17usize.pow(4)).map(|j| (j % 251) as u8).collect();
+    assert_eq!(h.finalize_boxed(32)[..], expected[..]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn io_copy() {
+    let mut source = std::io::Cursor::new(ptn(17usize.pow(4)));
+    let mut h = KangarooTwelve::new();
+    std::io::copy(&mut source, &mut h).unwrap();
+    assert_eq!(
+        h.finalize_boxed(32)[..],
+        hex!("8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe")[..]
+    );
+}
+
+#[test]
+fn chain() {
+    let c = ptn(41);
+    let mut output = [0u8; 32];
+    KangarooTwelve::new()
+        .chain_update(b"")
+        .chain_customization(&c)
+        .chain_update(b"\xFF")
+        .finalize_xof()
+        .read(&mut output);
+    assert_eq!(
+        output,
+        hex!("d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4")
+    );
+}
+
+#[test]
+fn multiple_reads() {
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    let mut output = [0u8; 32];
+    reader.read(&mut output);
+    assert_eq!(
+        output,
+        hex!("1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5")
+    );
+
+    // Skip ahead to byte 10000 in uneven steps
+    let mut skipped = vec![0u8; 10000 - 32];
+    for chunk in skipped.chunks_mut(97) {
+        reader.read(chunk);
+    }
+    reader.read(&mut output);
+    assert_eq!(
+        output,
+        hex!("e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d")
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn squeeze_into_writer() {
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    let mut output = Vec::new();
+    reader.squeeze_into_writer(&mut output, 10).unwrap();
+    reader.squeeze_into_writer(&mut output, 10022).unwrap();
+    assert_eq!(output.len(), 10032);
+    assert_eq!(output[..], digest_and_box::<KangarooTwelve>(b"", 10032)[..]);
+}
+
+#[test]
+fn seek() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    let mut output = [0u8; 32];
+
+    for &pos in &[10000, 0, 168, 167, 336, 5000, 135] {
+        reader.seek(pos);
+        reader.read(&mut output);
+        assert_eq!(output[..], expected[pos as usize..pos as usize + 32]);
+    }
+
+    reader.rewind();
+    reader.read(&mut output);
+    assert_eq!(output[..], expected[..32]);
+}
+
+#[test]
+fn bytes_read() {
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    assert_eq!(reader.bytes_read(), 0);
+    reader.read(&mut [0u8; 100]);
+    assert_eq!(reader.bytes_read(), 100);
+    reader.skip(1000);
+    assert_eq!(reader.bytes_read(), 1100);
+    reader.seek(5);
+    assert_eq!(reader.bytes_read(), 5);
+}
+
+#[test]
+fn skip() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    let mut output = [0u8; 32];
+
+    reader.skip(5);
+    reader.read(&mut output[..3]);
+    assert_eq!(output[..3], expected[5..8]);
+
+    reader.skip(10000 - 8);
+    reader.read(&mut output);
+    assert_eq!(output[..], expected[10000..]);
+}
+
+#[test]
+fn clone_reader() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    reader.skip(200);
+
+    let mut fork = reader.clone();
+    let mut a = [0u8; 500];
+    let mut b = [0u8; 500];
+    reader.read(&mut a);
+    fork.read(&mut b);
+    assert_eq!(a[..], expected[200..700]);
+    assert_eq!(a[..], b[..]);
+}
+
+#[test]
+fn iterators() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+
+    let bytes: Vec<u8> = reader.bytes().take(200).collect();
+    assert_eq!(bytes[..], expected[..200]);
+
+    for (i, chunk) in reader.chunks::<64>().take(100).enumerate() {
+        let pos = 200 + 64 * i;
+        assert_eq!(chunk[..], expected[pos..pos + 64]);
+    }
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn read_vec() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    assert_eq!(reader.read_vec(64)[..], expected[..64]);
+    assert_eq!(reader.read_boxed(64)[..], expected[64..128]);
+}
+
+#[test]
+#[rustfmt::skip]
+fn derive() {
+    let hasher = KangarooTwelve::new();
+    let mut enc_key = [0u8; 32];
+    hasher.derive(b"encryption key", &mut enc_key);
+    assert_eq!(
+        enc_key,
+        hex!("02066f1c80a6130bf5d06e808347360793adc456be118ee045556d115d0ef95f")
+    );
+    let mut mac_key = [0u8; 32];
+    hasher.derive(b"mac key", &mut mac_key);
+    assert_eq!(
+        mac_key,
+        hex!("ee82306e1ac943adc48c2dd7dabd0ed98a58bc014dc48e56205eb063deb09b94")
+    );
+
+    let mut iv = [0u8; 16];
+    KangarooTwelve::new_with_customization(b"cc")
+        .chain_update(ptn(17usize.pow(4)))
+        .derive(b"iv", &mut iv);
+    assert_eq!(iv, hex!("652b89ea09ca3f6d2a9e73e21b12633a"));
+
+    let mut output = [0u8; 32];
+    KangarooTwelve::new()
+        .chain_update(b"hello")
+        .derive(b"", &mut output);
+    assert_eq!(
+        output,
+        hex!("2d2dee027aa3898eaf0723028947d76dfdc802cd970f1ff6fd9343d1f136ed5d")
+    );
+
+    // The label is framed apart from the customization string
+    let mut framed = [0u8; 32];
+    KangarooTwelve::new_with_customization(b"b").derive(b"a", &mut framed);
+    let mut joined = [0u8; 32];
+    KangarooTwelve::new().derive(b"ab", &mut joined);
+    assert_ne!(framed, joined);
+}
+
+#[test]
+fn derive_apart_from_digest() {
+    // Knowing the digest gives no derived output
+    let secret = ptn(100);
+    let digest = digest_and_box::<KangarooTwelve>(&secret, 32);
+    let mut derived = [0u8; 32];
+    KangarooTwelve::new()
+        .chain_update(&secret)
+        .derive(b"mac key", &mut derived);
+    assert_ne!(derived[..], digest[..]);
+
+    let mut from_digest = [0u8; 32];
+    k12::kdf(b"mac key", &digest, &mut from_digest);
+    assert_ne!(derived, from_digest);
+    let mut customized = [0u8; 32];
+    KangarooTwelve::new_with_customization(b"mac key")
+        .chain_update(&digest)
+        .finalize_xof_into(&mut customized);
+    assert_ne!(derived, customized);
+}
+
+#[test]
+#[cfg(feature = "rand_core")]
+fn rng() {
+    use k12::rand_core::RngCore;
+
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut rng = KangarooTwelve::new().finalize_xof();
+    assert_eq!(rng.next_u32().to_le_bytes()[..], expected[..4]);
+    assert_eq!(rng.next_u64().to_le_bytes()[..], expected[4..12]);
+
+    let mut output = [0u8; 20];
+    rng.fill_bytes(&mut output);
+    assert_eq!(output[..], expected[12..32]);
+}
+
+#[test]
+#[cfg(feature = "rand_core")]
+#[rustfmt::skip]
+fn k12_rng() {
+    use k12::{rand_core::{RngCore, SeedableRng}, K12Rng};
+
+    let seed = [
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+        16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+    ];
+    let expected = hex!("
+        2abff1023c02ff3b8c16217f2b5a7d675d7e8325963d4e5c7af43f41e2914408
+        8746109232af120e33b4134bac402059f0c4e399f91a3560d28ab4533846bf83
+        a2df04dfe0ad5aa1716a2d8b0bfccaf2
+    ");
+    let mut rng = K12Rng::from_seed(seed);
+    assert_eq!(rng.next_u32().to_le_bytes()[..], expected[..4]);
+    assert_eq!(rng.next_u64().to_le_bytes()[..], expected[4..12]);
+    let mut output = [0u8; 68];
+    rng.fill_bytes(&mut output);
+    assert_eq!(output[..], expected[12..]);
+
+    let mut output = [0u8; 32];
+    K12Rng::from_seed_bytes(&ptn(17usize.pow(4))).fill_bytes(&mut output);
+    assert_eq!(
+        output,
+        hex!("a89a2e90375ed0c6bb310182a3bcc6513c9c32cb627cc82f414704aa1cf7c741")
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn read_vectored() {
+    use std::io::IoSliceMut;
+
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    let (mut a, mut b, mut c) = ([0u8; 10], [0u8; 300], [0u8; 7]);
+    reader.read_vectored(&mut [
+        IoSliceMut::new(&mut a),
+        IoSliceMut::new(&mut b),
+        IoSliceMut::new(&mut c),
+    ]);
+    assert_eq!(a[..], expected[..10]);
+    assert_eq!(b[..], expected[10..310]);
+    assert_eq!(c[..], expected[310..317]);
+}
+
+#[test]
+fn peek() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    let mut peeked = [0u8; 200];
+    let mut output = [0u8; 200];
+
+    reader.skip(100);
+    reader.peek(&mut peeked);
+    reader.read(&mut output);
+    assert_eq!(peeked, output);
+    assert_eq!(output[..], expected[100..300]);
+    assert_eq!(reader.bytes_read(), 300);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn io_read() {
+    use std::io::{self, Read};
+
+    let expected = digest_and_box::<KangarooTwelve>(b"", 10032);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+
+    let mut output = [0u8; 32];
+    io::Read::read_exact(&mut reader, &mut output).unwrap();
+    assert_eq!(output[..], expected[..32]);
+
+    let mut rest = Vec::new();
+    io::copy(&mut Read::take(reader, 10000), &mut rest).unwrap();
+    assert_eq!(rest[..], expected[32..]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn buf_read() {
+    use std::io::BufRead;
+
+    let expected = digest_and_box::<KangarooTwelve>(b"", 400);
+    let mut reader = KangarooTwelve::new().finalize_xof().buffered();
+
+    assert_eq!(reader.fill_buf().unwrap(), &expected[..168]);
+    reader.consume(100);
+    assert_eq!(reader.fill_buf().unwrap(), &expected[100..168]);
+    reader.consume(68);
+    assert_eq!(reader.fill_buf().unwrap(), &expected[168..336]);
+    reader.consume(1);
+
+    let mut output = [0u8; 231];
+    reader.read(&mut output);
+    assert_eq!(output[..], expected[169..]);
+}
+
+#[test]
+fn limit() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 200);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    reader.skip(50);
+    let mut take = reader.limit(100);
+
+    let mut output = [0u8; 80];
+    assert_eq!(take.read(&mut output), 80);
+    assert_eq!(output[..], expected[50..130]);
+    assert_eq!(take.limit(), 20);
+    assert_eq!(take.read(&mut output), 20);
+    assert_eq!(output[..20], expected[130..150]);
+    assert_eq!(take.read(&mut output), 0);
+
+    let mut reader = take.into_inner();
+    reader.read(&mut output[..50]);
+    assert_eq!(output[..50], expected[150..]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn limit_io_read() {
+    use std::io::Read;
+
+    let expected = digest_and_box::<KangarooTwelve>(b"", 1000);
+    let mut output = Vec::new();
+    KangarooTwelve::new()
+        .finalize_xof()
+        .limit(1000)
+        .read_to_end(&mut output)
+        .unwrap();
+    assert_eq!(output[..], expected[..]);
+}
+
+#[test]
+fn arrays() {
+    let expected = digest_and_box::<KangarooTwelve>(b"abc", 96);
+    let array: [u8; 32] = KangarooTwelve::new()
+        .chain_update(b"abc")
+        .finalize_xof_array();
+    assert_eq!(array[..], expected[..32]);
+
+    let mut reader = KangarooTwelve::new().chain_update(b"abc").finalize_xof();
+    assert_eq!(reader.read_array::<32>()[..], expected[..32]);
+    assert_eq!(reader.read_array::<64>()[..], expected[32..]);
+}
+
+#[test]
+fn finalize_xof_limited() {
+    let expected = digest_and_box::<KangarooTwelve>(b"", 64);
+    let mut reader = KangarooTwelve::new().finalize_xof_limited(64);
+
+    let mut output = [0u8; 48];
+    reader.try_read(&mut output).unwrap();
+    assert_eq!(output[..], expected[..48]);
+    assert!(reader.try_read(&mut output).is_err());
+    assert_eq!(reader.limit(), 16);
+    reader.try_read(&mut output[..16]).unwrap();
+    assert_eq!(output[..16], expected[48..]);
+    assert!(reader.try_read(&mut output[..1]).is_err());
+    reader.try_read(&mut []).unwrap();
+}
+
+#[test]
+fn buffered_reader() {
+    let expected = digest_and_box::<KangarooTwelve>(b"abc", 1000);
+    let mut reader = KangarooTwelve::new()
+        .chain_update(b"abc")
+        .finalize_xof()
+        .buffered();
+
+    let mut output = vec![0u8; 1000];
+    let mut pos = 0;
+    for len in (1..9).chain(Some(400)).cycle() {
+        let end = (pos + len).min(output.len());
+        reader.read(&mut output[pos..end]);
+        pos = end;
+        if pos == output.len() {
+            break;
+        }
+    }
+    assert_eq!(output[..], expected[..]);
+}
+
+#[test]
+#[cfg(feature = "zeroize")]
+fn zeroize_on_drop() {
+    fn assert_zeroize_on_drop<T: k12::zeroize::ZeroizeOnDrop>(_: &T) {}
+
+    let hasher = KangarooTwelve::new().chain_update(b"secret");
+    assert_zeroize_on_drop(&hasher);
+    let reader = hasher.finalize_xof();
+    assert_zeroize_on_drop(&reader);
+    let reader = reader.buffered();
+    assert_zeroize_on_drop(&reader);
+    let reader = reader.into_inner().limit(32);
+    assert_zeroize_on_drop(&reader);
+}
+
+#[test]
+#[cfg(feature = "uninit")]
+fn read_uninit() {
+    use core::mem::MaybeUninit;
+
+    let expected = digest_and_box::<KangarooTwelve>(b"", 500);
+    let mut reader = KangarooTwelve::new().finalize_xof();
+    let mut output = [MaybeUninit::<u8>::uninit(); 500];
+    let (head, tail) = output.split_at_mut(100);
+    assert_eq!(reader.read_uninit(head), &expected[..100]);
+    assert_eq!(reader.read_uninit(tail), &expected[100..]);
+    assert_eq!(reader.bytes_read(), 500);
+}
+
+#[test]
+fn whole_leaves() {
+    // Batches of whole chunks, preceded by partial chunks or not, must hash
+    // the same as chunk-sized pieces fed one by one
+    let input = ptn(80 * 8192 + 1234);
+    for &offset in &[0, 100, 8192, 8192 + 100, 3 * 8192] {
+        let mut h = KangarooTwelve::new_with_customization(b"leaves");
+        h.update(&input[..offset]);
+        h.update(&input[offset..]);
+
+        let mut expected = KangarooTwelve::new_with_customization(b"leaves");
+        for piece in input.chunks(1000) {
+            expected.update(piece);
+        }
+        assert_eq!(h.finalize_boxed(32), expected.finalize_boxed(32));
+    }
+}
+
+#[test]
+#[cfg(any(feature = "parallel", feature = "threads"))]
+fn with_threads() {
+    let input = ptn(40 * 8192 + 17);
+    let expected = KangarooTwelve::new()
+        .chain_update(&input)
+        .finalize_boxed(32);
+    for &threads in &[0, 1, 2, 3, 64] {
+        let mut h = KangarooTwelve::new().with_threads(threads);
+        h.update(&input);
+        assert_eq!(h.finalize_boxed_reset(32), expected);
+        // The limit survives the reset
+        h.update(&input);
+        assert_eq!(h.finalize_boxed(32), expected);
+    }
+}
+
+#[test]
+#[cfg(any(feature = "parallel", feature = "threads"))]
+fn with_threads_many_leaves() {
+    // More whole chunks than fit in one stack batch, not starting at a leaf
+    // boundary, on more threads than this machine may have CPUs
+    let input = ptn(300 * 8192 + 4321);
+    for &split in &[0, 1000, 8192 + 1000] {
+        let mut expected = KangarooTwelve::new_with_customization(b"threads");
+        for piece in input.chunks(1000) {
+            expected.update(piece);
+        }
+        let expected = expected.finalize_boxed(32);
+        for &threads in &[2, 4, 7] {
+            let mut h = KangarooTwelve::new_with_customization(b"threads").with_threads(threads);
+            h.update(&input[..split]);
+            h.update(&input[split..]);
+            assert_eq!(h.finalize_boxed(32), expected, "{} threads", threads);
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "threads")]
+fn pipelined() {
+    let input = ptn(30 * 8192 + 4321);
+    for &split in &[0, 1000, 8192, 8192 + 1000, 20 * 8192] {
+        let expected = KangarooTwelve::new_with_customization(b"pipe")
+            .chain_update(&input)
+            .finalize_boxed(32);
+
+        let mut h = KangarooTwelve::new_with_customization(b"pipe");
+        h.update(&input[..split]);
+        let mut h = h.pipelined(3);
+        for piece in input[split..].chunks(5000) {
+            h.update(piece);
+        }
+        assert_eq!(h.finalize_boxed(32), expected);
+    }
+
+    // Short messages never reach the workers
+    let h = KangarooTwelve::new().pipelined(0).chain(b"abc");
+    assert_eq!(
+        h.finalize_boxed(32),
+        digest_and_box::<KangarooTwelve>(b"abc", 32)
+    );
+}
+
+#[test]
+fn send_to_blocking_pool() {
+    fn assert_send_static<T: Send + 'static>(_: &T) {}
+
+    let buffer = ptn(20_000);
+    let hash = move || {
+        KangarooTwelve::new()
+            .chain_update(&buffer)
+            .finalize_boxed(32)
+    };
+    assert_send_static(&hash);
+    assert_eq!(hash(), digest_and_box::<KangarooTwelve>(&ptn(20_000), 32));
+}
+
+#[test]
+fn executor() {
+    use k12::Executor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting(AtomicUsize);
+
+    impl Executor for Counting {
+        fn join(&self, a: &mut (dyn FnMut() + Send), b: &mut (dyn FnMut() + Send)) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            b();
+            a();
+        }
+    }
+
+    let input = ptn(100 * 8192 + 5);
+    let expected = KangarooTwelve::new()
+        .chain_update(&input)
+        .finalize_boxed(32);
+    let executor = Counting(AtomicUsize::new(0));
+    let mut h = KangarooTwelve::new().with_executor(&executor);
+    h.update(&input);
+    assert_eq!(h.finalize_boxed_reset(32), expected);
+    let joins = executor.0.load(Ordering::Relaxed);
+    assert!(joins > 0);
+
+    // The executor survives the reset
+    h.update(&input);
+    assert_eq!(h.finalize_boxed(32), expected);
+    assert_eq!(executor.0.load(Ordering::Relaxed), 2 * joins);
+}
+
+#[test]
+fn with_backend() {
+    use k12::Backend;
+
+    let input = ptn(21 * 8192 + 5);
+    let expected = KangarooTwelve::new()
+        .chain_update(&input)
+        .finalize_boxed(32);
+    let all = [
+        Backend::Scalar,
+        Backend::Sse2,
+        Backend::Avx2,
+        Backend::Avx512,
+        Backend::Neon,
+        Backend::Sha3,
+        Backend::Simd,
+    ];
+    assert!(Backend::detect().is_available());
+    assert_eq!(Backend::available().last(), Some(Backend::Scalar));
+    for &backend in &all {
+        match KangarooTwelve::new().with_backend(backend) {
+            Ok(h) => {
+                assert!(backend.is_available());
+                let mut h = h.chain_update(&input);
+                assert_eq!(h.finalize_boxed_reset(32), expected, "{:?}", backend);
+                h.update(&input);
+                assert_eq!(h.finalize_boxed(32), expected, "{:?}", backend);
+            }
+            Err(err) => {
+                assert!(!backend.is_available());
+                assert_eq!(err, k12::UnsupportedBackend(backend));
+            }
+        }
+    }
+}
+
+#[test]
+fn executor_task_size() {
+    use k12::Executor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Sized(usize, AtomicUsize);
+
+    impl Executor for Sized {
+        fn join(&self, a: &mut (dyn FnMut() + Send), b: &mut (dyn FnMut() + Send)) {
+            self.1.fetch_add(1, Ordering::Relaxed);
+            a();
+            b();
+        }
+
+        fn min_leaves_per_task(&self) -> usize {
+            self.0
+        }
+    }
+
+    let input = ptn(65 * 8192);
+    let expected = KangarooTwelve::new()
+        .chain_update(&input)
+        .finalize_boxed(32);
+    let mut joins = Vec::new();
+    for &min in &[0, 1, 4, 32, 64] {
+        let executor = Sized(min, AtomicUsize::new(0));
+        let h = KangarooTwelve::new()
+            .with_executor(&executor)
+            .chain_update(&input);
+        assert_eq!(h.finalize_boxed(32), expected);
+        joins.push(executor.1.load(Ordering::Relaxed));
+    }
+    // 0 is treated as 1, and 64 leaves are never split into tasks of 64
+    assert_eq!(joins[0], joins[1]);
+    assert!(joins[1] > joins[2] && joins[2] > joins[3] && joins[3] > 0);
+    assert_eq!(joins[4], 0);
+}
+
+#[test]
+#[cfg(feature = "threads")]
+fn scoped_thread_executor() {
+    use k12::Executor;
+
+    struct ScopedThreads;
+
+    impl Executor for ScopedThreads {
+        fn join(&self, a: &mut (dyn FnMut() + Send), b: &mut (dyn FnMut() + Send)) {
+            std::thread::scope(|s| {
+                s.spawn(a);
+                b();
+            });
+        }
+    }
+
+    let input = ptn(70 * 8192);
+    let expected = KangarooTwelve::new()
+        .chain_update(&input)
+        .finalize_boxed(32);
+    let h = KangarooTwelve::new()
+        .with_executor(&ScopedThreads)
+        .chain_update(&input);
+    assert_eq!(h.finalize_boxed(32), expected);
+}
+
+#[test]
+fn distributed() {
+    use k12::{chaining_values, CHUNK_SIZE};
+
+    let message = ptn(20 * CHUNK_SIZE + 77);
+    let customization = b"distributed";
+    let expected = KangarooTwelve::new_with_customization(customization)
+        .chain_update(&message)
+        .finalize_boxed(32);
+
+    // Absorb the message in pieces alternating between local and remote
+    let mut h = KangarooTwelve::new_with_customization(customization);
+    h.update(&message[..CHUNK_SIZE]);
+    let mut pos = CHUNK_SIZE;
+    for &(local, remote) in &[(0, 3), (2 * CHUNK_SIZE, 0), (CHUNK_SIZE, 10)] {
+        h.update(&message[pos..pos + local]);
+        pos += local;
+        let mut cvs = vec![[0u8; 32]; remote];
+        chaining_values(&message[pos..pos + remote * CHUNK_SIZE], &mut cvs);
+        h.absorb_chaining_values(&cvs);
+        pos += remote * CHUNK_SIZE;
+    }
+    h.update(&message[pos..]);
+    assert_eq!(h.message_len(), message.len() as u64);
+    assert_eq!(h.finalize_boxed(32), expected);
+}
+
+#[test]
+#[should_panic]
+fn distributed_misaligned() {
+    let mut h = KangarooTwelve::new();
+    h.update(&ptn(k12::CHUNK_SIZE + 1));
+    h.absorb_chaining_values(&[[0u8; 32]]);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn hash_batch() {
+    let inputs: Vec<Vec<u8>> = (0..50).map(|i| ptn(i * i * 97)).collect();
+    let messages: Vec<&[u8]> = inputs.iter().map(|input| &input[..]).collect();
+    let digests = k12::hash_batch(&messages);
+    assert_eq!(digests.len(), messages.len());
+    for (message, digest) in messages.iter().zip(&digests) {
+        assert_eq!(
+            digest[..],
+            digest_and_box::<KangarooTwelve>(message, 32)[..]
+        );
+    }
+
+    let mut digests2 = vec![[0u8; 32]; messages.len()];
+    k12::hash_batch_into(&messages, &mut digests2);
+    assert_eq!(digests2, digests);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn concurrent_hasher() {
+    use k12::{ChunkError, ConcurrentHasher, CHUNK_SIZE};
+
+    for &len in &[
+        0,
+        100,
+        CHUNK_SIZE,
+        CHUNK_SIZE + 1,
+        2 * CHUNK_SIZE,
+        7 * CHUNK_SIZE + 9,
+    ] {
+        let message = ptn(len);
+        let expected = KangarooTwelve::new_with_customization(b"cc")
+            .chain_update(&message)
+            .finalize_boxed(32);
+
+        let hasher = ConcurrentHasher::new_with_customization(len as u64, b"cc");
+        let chunks: Vec<&[u8]> = match len {
+            0 => vec![&[]],
+            _ => message.chunks(CHUNK_SIZE).collect(),
+        };
+        assert_eq!(hasher.chunk_count(), chunks.len() as u64);
+        // Submit in reverse order
+        for (index, chunk) in chunks.iter().enumerate().rev() {
+            hasher.submit(index as u64, chunk).unwrap();
+        }
+        let mut output = vec![0u8; 32];
+        hasher.finalize_xof().unwrap().read(&mut output);
+        assert_eq!(output[..], expected[..]);
+    }
+
+    let message = ptn(3 * CHUNK_SIZE + 5);
+    let hasher = ConcurrentHasher::new(message.len() as u64);
+    assert_eq!(
+        hasher.submit(4, &[]),
+        Err(ChunkError::OutOfRange { index: 4 })
+    );
+    assert_eq!(
+        hasher.submit(3, &message[..6]),
+        Err(ChunkError::InvalidLength { index: 3 })
+    );
+    hasher
+        .submit(1, &message[CHUNK_SIZE..2 * CHUNK_SIZE])
+        .unwrap();
+    assert_eq!(
+        hasher.submit(1, &message[CHUNK_SIZE..2 * CHUNK_SIZE]),
+        Err(ChunkError::Duplicate { index: 1 })
+    );
+    hasher.submit(0, &message[..CHUNK_SIZE]).unwrap();
+    hasher.submit(3, &message[3 * CHUNK_SIZE..]).unwrap();
+    assert_eq!(
+        hasher.submit(1, &message[CHUNK_SIZE..2 * CHUNK_SIZE]),
+        Err(ChunkError::Duplicate { index: 1 })
+    );
+    assert_eq!(
+        hasher.finalize_xof().unwrap_err(),
+        ChunkError::Missing { index: 2 }
+    );
+
+    // Nothing is allocated up front for the chunks of huge messages
+    let hasher = ConcurrentHasher::new(u64::MAX);
+    assert_eq!(hasher.chunk_count(), u64::MAX / CHUNK_SIZE as u64 + 1);
+    hasher
+        .submit(5, &message[CHUNK_SIZE..2 * CHUNK_SIZE])
+        .unwrap();
+    assert_eq!(
+        hasher.finalize_xof().unwrap_err(),
+        ChunkError::Missing { index: 0 }
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn chunk_tree() {
+    use k12::{ChunkTree, CHUNK_SIZE};
+
+    for &len in &[
+        0,
+        100,
+        CHUNK_SIZE,
+        CHUNK_SIZE + 1,
+        2 * CHUNK_SIZE,
+        7 * CHUNK_SIZE + 9,
+    ] {
+        let message = ptn(len);
+        let expected = KangarooTwelve::new_with_customization(b"cc")
+            .chain_update(&message)
+            .finalize_boxed(100);
+
+        let tree = ChunkTree::new_with_customization(&message, b"cc");
+        assert!(tree.verify_digest(&expected));
+        assert!(tree.verify_digest(&expected[..32]));
+        assert!(!tree.verify_digest(&expected[1..]));
+        assert!(!tree.verify_digest(&[]));
+        assert!(!tree.verify_digest(&expected[..1]));
+        assert!(!tree.verify_digest(&expected[..31]));
+
+        let chunks: Vec<&[u8]> = match len {
+            0 => vec![&[]],
+            _ => message.chunks(CHUNK_SIZE).collect(),
+        };
+        assert_eq!(tree.chunk_count(), chunks.len() as u64);
+        for (index, chunk) in chunks.iter().enumerate() {
+            tree.verify_chunk(index as u64, chunk).unwrap();
+        }
+
+        let received = ChunkTree::from_parts(
+            len as u64,
+            b"cc",
+            tree.first_chunk().to_vec(),
+            tree.chaining_values().to_vec(),
+            tree.last_chunk().to_vec(),
+        );
+        assert_eq!(received, Ok(tree));
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn chunk_tree_short_digest() {
+    use k12::{ChunkTree, CHUNK_SIZE};
+
+    // A forged tree is not accepted against an empty or truncated digest,
+    // even one it matches
+    let digest = digest_and_box::<KangarooTwelve>(&ptn(3 * CHUNK_SIZE), 32);
+    let forged = ChunkTree::new(&[0; 3 * CHUNK_SIZE]);
+    let forged_digest = forged.finalize_xof().read_boxed(32);
+    assert!(!forged.verify_digest(&[]));
+    assert!(!forged.verify_digest(&digest[..1]));
+    assert!(!forged.verify_digest(&forged_digest[..1]));
+    assert!(!forged.verify_digest(&forged_digest[..31]));
+    assert!(forged.verify_digest(&forged_digest));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn chunk_verifier() {
+    use k12::{ChunkError, ChunkTree, CHUNK_SIZE};
+
+    for &len in &[0, 100, CHUNK_SIZE, 2 * CHUNK_SIZE, 7 * CHUNK_SIZE + 9] {
+        let message = ptn(len);
+        let tree = ChunkTree::new_with_customization(&message, b"cc");
+        for &step in &[1, 1000, CHUNK_SIZE, CHUNK_SIZE + 1] {
+            let mut verifier = tree.verifier();
+            for part in message.chunks(step) {
+                verifier.update(part).unwrap();
+            }
+            assert_eq!(verifier.position(), len as u64);
+            assert_eq!(
+                verifier.update(b"x"),
+                Err(ChunkError::OutOfRange {
+                    index: tree.chunk_count()
+                })
+            );
+        }
+
+        let mut verifier = tree.verifier();
+        verifier.update(&message).unwrap();
+        verifier.finish().unwrap();
+    }
+
+    let message = ptn(4 * CHUNK_SIZE + 5);
+    let tree = ChunkTree::new(&message);
+    for index in 0..5 {
+        // Copied chunks are rejected at the first bad byte, the others at
+        // their end
+        let mut corrupted = message.clone();
+        corrupted[index * CHUNK_SIZE + 3] ^= 1;
+        let mut verifier = tree.verifier_at(index as u64).unwrap();
+        let mut rejected_at = None;
+        for (offset, byte) in corrupted[index * CHUNK_SIZE..].iter().enumerate() {
+            if verifier.update(&[*byte]).is_err() {
+                rejected_at = Some(offset);
+                break;
+            }
+        }
+        let expected = match index {
+            0 | 4 => 3,
+            _ => CHUNK_SIZE - 1,
+        };
+        assert_eq!(rejected_at, Some(expected));
+        let error = ChunkError::Mismatch {
+            index: index as u64,
+        };
+        assert_eq!(verifier.update(&[]), Err(error));
+        assert_eq!(verifier.finish(), Err(error));
+    }
+
+    let mut verifier = tree.verifier_at(2).unwrap();
+    verifier
+        .update(&message[2 * CHUNK_SIZE..3 * CHUNK_SIZE + 7])
+        .unwrap();
+    assert_eq!(verifier.chunk_index(), 3);
+    assert_eq!(verifier.position(), 3 * CHUNK_SIZE as u64 + 7);
+    assert_eq!(verifier.finish(), Err(ChunkError::Missing { index: 3 }));
+    assert_eq!(
+        tree.verifier_at(5).unwrap_err(),
+        ChunkError::OutOfRange { index: 5 }
+    );
+
+    let mut verifier = tree.verifier();
+    std::io::copy(&mut &message[..], &mut verifier).unwrap();
+    verifier.finish().unwrap();
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn chunk_tree_errors() {
+    use k12::{ChunkError, ChunkTree, CHUNK_SIZE};
+
+    let message = ptn(4 * CHUNK_SIZE + 5);
+    let tree = ChunkTree::new(&message);
+    assert_eq!(
+        tree.verify_chunk(5, &[]),
+        Err(ChunkError::OutOfRange { index: 5 })
+    );
+    assert_eq!(
+        tree.verify_chunk(4, &message[..6]),
+        Err(ChunkError::InvalidLength { index: 4 })
+    );
+    for index in 0..5 {
+        let mut chunk = message.chunks(CHUNK_SIZE).nth(index).unwrap().to_vec();
+        chunk[3] ^= 1;
+        assert_eq!(
+            tree.verify_chunk(index as u64, &chunk),
+            Err(ChunkError::Mismatch {
+                index: index as u64
+            })
+        );
+    }
+
+    let parts = |cvs: usize, last: usize| {
+        ChunkTree::from_parts(
+            message.len() as u64,
+            &[],
+            tree.first_chunk().to_vec(),
+            tree.chaining_values()[..cvs].to_vec(),
+            tree.last_chunk()[..last].to_vec(),
+        )
+    };
+    assert_eq!(parts(1, 5), Err(ChunkError::Missing { index: 2 }));
+    assert_eq!(parts(3, 4), Err(ChunkError::InvalidLength { index: 4 }));
+    let mut cvs = tree.chaining_values().to_vec();
+    cvs.push([0; 32]);
+    assert_eq!(
+        ChunkTree::from_parts(
+            message.len() as u64,
+            &[],
+            tree.first_chunk().to_vec(),
+            cvs,
+            tree.last_chunk().to_vec(),
+        ),
+        Err(ChunkError::OutOfRange { index: 4 })
+    );
+}
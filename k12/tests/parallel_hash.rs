@@ -0,0 +1,155 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, ExtendableOutputReset, Reset, Update},
+    ParallelHash128, ParallelHash256,
+};
+
+mod common;
+use common::ptn;
+
+/// Message of samples 1, 2, 4 and 5 of NIST SP 800-185.
+const X: [u8; 24] = hex!("000102030405060710111213141516172021222324252627");
+
+/// Message of samples 3 and 6 of NIST SP 800-185.
+#[rustfmt::skip]
+const X3: [u8; 72] = hex!("
+    000102030405060708090a0b101112131415161718191a1b
+    202122232425262728292a2b303132333435363738393a3b
+    404142434445464748494a4b505152535455565758595a5b
+");
+
+fn parallel_hash128(data: &[u8], b: usize, s: &[u8], n: usize) -> Vec<u8> {
+    let mut output = vec![0u8; n];
+    ParallelHash128::new(b, s)
+        .chain(data)
+        .finalize_into(&mut output);
+    output
+}
+
+fn parallel_hash256(data: &[u8], b: usize, s: &[u8], n: usize) -> Vec<u8> {
+    let mut output = vec![0u8; n];
+    ParallelHash256::new(b, s)
+        .chain(data)
+        .finalize_into(&mut output);
+    output
+}
+
+#[test]
+#[rustfmt::skip]
+fn parallel_hash128_samples() {
+    // Source: NIST SP 800-185 examples
+    assert_eq!(
+        parallel_hash128(&X, 8, b"", 32)[..],
+        hex!("ba8dc1d1d979331d3f813603c67f72609ab5e44b94a0b8f9af46514454a2b4f5")[..]
+    );
+    assert_eq!(
+        parallel_hash128(&X, 8, b"Parallel Data", 32)[..],
+        hex!("fc484dcb3f84dceedc353438151bee58157d6efed0445a81f165e495795b7206")[..]
+    );
+    assert_eq!(
+        parallel_hash128(&X3, 12, b"Parallel Data", 32)[..],
+        hex!("f7fd5312896c6685c828af7e2adb97e393e7f8d54e3c2ea4b95e5aca3796e8fc")[..]
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn parallel_hash256_samples() {
+    // Source: NIST SP 800-185 examples
+    assert_eq!(
+        parallel_hash256(&X, 8, b"", 64)[..],
+        hex!("
+            bc1ef124da34495e948ead207dd9842235da432d2bbc54b4c110e64c45110553
+            1b7f2a3e0ce055c02805e7c2de1fb746af97a1dd01f43b824e31b87612410429
+        ")[..]
+    );
+    assert_eq!(
+        parallel_hash256(&X, 8, b"Parallel Data", 64)[..],
+        hex!("
+            cdf15289b54f6212b4bc270528b49526006dd9b54e2b6add1ef6900dda3963bb
+            33a72491f236969ca8afaea29c682d47a393c065b38e29fae651a2091c833110
+        ")[..]
+    );
+    assert_eq!(
+        parallel_hash256(&X3, 12, b"Parallel Data", 64)[..],
+        hex!("
+            69d0fcb764ea055dd09334bc6021cb7e4b61348dff375da262671cdec3effa8d
+            1b4568a6cce16b1cad946ddde27f6ce2b8dee4cd1b24851ebf00eb90d43813e9
+        ")[..]
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn parallel_hash_xof_samples() {
+    // Source: NIST SP 800-185 examples
+    assert_eq!(
+        ParallelHash128::new(8, b"").chain(X).finalize_boxed(32)[..],
+        hex!("fe47d661e49ffe5b7d999922c062356750caf552985b8e8ce6667f2727c3c8d3")[..]
+    );
+    assert_eq!(
+        ParallelHash256::new(8, b"").chain(X).finalize_boxed(64)[..],
+        hex!("
+            c10a052722614684144d28474850b410757e3cba87651ba167a5cbddff7f4666
+            75fbf84bcae7378ac444be681d729499afca667fb879348bfdda427863c82f1c
+        ")[..]
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn parallel_hash_long_messages() {
+    let m = ptn(100_000);
+    assert_eq!(
+        parallel_hash128(b"", 8, b"", 32)[..],
+        hex!("96427c30224408859f95e89e4fa84e1c7a1478dbf2008ac982ce61a77f37a272")[..]
+    );
+    assert_eq!(
+        parallel_hash128(&m, 8192, b"Parallel Data", 32)[..],
+        hex!("23af541081ec1354a1eaccd3d82c454a37ea7f39f898597cde5fcd17cdbe8f8f")[..]
+    );
+    assert_eq!(
+        parallel_hash256(&m, 1000, b"", 64)[..],
+        hex!("
+            136b0bce6a6e8671540a9bb4e9236c5e74ebf6156dc3f0ec7abd1f026b7189f0
+            5e1953df4154689afa124b7f7cbb5c2bf8005469b751fd5b5191c50e44631dd5
+        ")[..]
+    );
+    assert_eq!(
+        ParallelHash128::new(17, b"").chain(&m).finalize_boxed(100)[68..],
+        hex!("290f2842e09aaacfa87141530a73a68bc1f6c4915946d7bca92afc2b3cdd78ba")[..]
+    );
+}
+
+#[test]
+fn parallel_hash_incremental() {
+    let m = ptn(100_000);
+    let expected = parallel_hash128(&m, 1000, b"Parallel Data", 32);
+    for &step in &[1, 7, 999, 1000, 1001, 65_536] {
+        let mut hasher = ParallelHash128::new(1000, b"Parallel Data");
+        for part in m.chunks(step) {
+            hasher.update(part);
+        }
+        let mut output = [0u8; 32];
+        hasher.finalize_into(&mut output);
+        assert_eq!(output[..], expected[..], "step {}", step);
+    }
+}
+
+#[test]
+fn parallel_hash_reset() {
+    let mut hasher = ParallelHash256::new(12, b"Parallel Data");
+    hasher.update(b"something else");
+    hasher.reset();
+    hasher.update(&X3[..30]);
+    let mut first = [0u8; 64];
+    hasher.finalize_xof_reset_into(&mut first);
+    hasher.update(&X3[..30]);
+    assert_eq!(first[..], hasher.finalize_boxed(64)[..]);
+}
+
+#[test]
+#[should_panic]
+fn parallel_hash_zero_block_size() {
+    ParallelHash128::new(0, b"");
+}
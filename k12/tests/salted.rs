@@ -0,0 +1,69 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, ExtendableOutputReset, Reset, Update},
+    K12Salted, KangarooTwelve,
+};
+
+mod common;
+use common::ptn;
+
+/// Salt of 32 bytes used by the test vectors.
+const SALT: [u8; 32] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31,
+];
+
+#[test]
+#[rustfmt::skip]
+fn salted_vectors() {
+    assert_eq!(
+        K12Salted::new(&SALT).finalize_boxed(32)[..],
+        hex!("13f78dfae6fa2420d51a32a2025236f6df0688f71ecc4131c1a764c3bb14ab05")[..]
+    );
+    assert_eq!(
+        K12Salted::new(&SALT).chain(b"message to sign").finalize_boxed(32)[..],
+        hex!("5dacb65b31883e19743f2b76cdc366a4e1676d07802d19d1039d9c110cc41424")[..]
+    );
+    assert_eq!(
+        K12Salted::new(b"").chain(b"message to sign").finalize_boxed(32)[..],
+        hex!("d2e412da12831e3ab2ff74a701e276df97a725662e5690974c4694321d27fa0e")[..]
+    );
+    assert_eq!(
+        K12Salted::new_with_customization(&SALT[..16], b"cc")
+            .chain(ptn(17usize.pow(4)))
+            .finalize_boxed(32)[..],
+        hex!("83e113bb2081e26b14715214d0dbc5373640c7ae0728265bdd14b5d6b2c02d1c")[..]
+    );
+}
+
+#[test]
+fn salted_is_not_unsalted() {
+    let unsalted = KangarooTwelve::new()
+        .chain(b"message to sign")
+        .finalize_boxed(32);
+    let salted = K12Salted::new(b"")
+        .chain(b"message to sign")
+        .finalize_boxed(32);
+    assert_ne!(salted, unsalted);
+    // The salt is framed apart from the message
+    assert_ne!(
+        K12Salted::new(b"ab").chain(b"c").finalize_boxed(32),
+        K12Salted::new(b"a").chain(b"bc").finalize_boxed(32)
+    );
+}
+
+#[test]
+fn salted_reset() {
+    let expected = K12Salted::new_with_customization(&SALT, b"cc")
+        .chain(b"message to sign")
+        .finalize_boxed(32);
+
+    let mut hasher = K12Salted::new_with_customization(&SALT, b"cc");
+    hasher.update(b"something else");
+    hasher.reset();
+    hasher.update(b"message to sign");
+    assert_eq!(hasher.finalize_boxed_reset(32), expected);
+    hasher.update(b"message to sign");
+    assert_eq!(hasher.salt(), &SALT[..]);
+    assert_eq!(hasher.finalize_boxed(32), expected);
+}
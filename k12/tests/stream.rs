@@ -0,0 +1,60 @@
+use hex_literal::hex;
+use k12::{digest::XofReader, K12Stream};
+
+mod common;
+use common::ptn;
+
+#[test]
+#[rustfmt::skip]
+fn keystream() {
+    let expected = hex!("
+        93df9f80536e591e115b9e2f7a9b14c9ad221a48c7732ef57b14cc7c7c11e969
+        3e61374402930ac045be1164a152edece68576f8c76f8417fad08473df865f5d
+        5bd2734797ee848a7af4ba3907ed7c6340a5b995f77e7cdc71bfa58b9ebc71b3
+        298fc54b7897548df3d819d9b27b990a20cb1039c5546f3116b407dab0424440
+        00f99d345d59d7503fd63fda5fabd3ad139fc97f874035500d5a7bae2b826a53
+        f435c7cd59aa8230e5589268dc7d6b491daf4d3783fb5fdb6c4e9e6adaa082b8
+        8f55c4aedf32511e
+    ");
+    let mut keystream = [0u8; 200];
+    K12Stream::new(&ptn(32), &ptn(12)).read(&mut keystream);
+    assert_eq!(keystream[..], expected[..]);
+
+    let mut keystream = [0u8; 32];
+    K12Stream::new(&ptn(32), b"").read(&mut keystream);
+    assert_eq!(
+        keystream,
+        hex!("9a76f6cc154f003c72c770445bd33d9588baa693c9bccfcd9e35b1494eb105af")
+    );
+}
+
+#[test]
+fn apply_and_seek() {
+    let (key, nonce) = (ptn(32), ptn(12));
+    let mut keystream = [0u8; 1000];
+    K12Stream::new(&key, &nonce).read(&mut keystream);
+
+    let mut data = ptn(1000);
+    let mut stream = K12Stream::new(&key, &nonce);
+    for piece in data.chunks_mut(333) {
+        stream.apply_keystream(piece);
+    }
+    assert_eq!(stream.position(), 1000);
+    for ((&c, &p), &k) in data.iter().zip(ptn(1000).iter()).zip(keystream.iter()) {
+        assert_eq!(c, p ^ k);
+    }
+
+    stream.seek(500);
+    stream.apply_keystream(&mut data[500..]);
+    stream.seek(0);
+    stream.apply_keystream(&mut data[..500]);
+    assert_eq!(data, ptn(1000));
+}
+
+#[test]
+fn nonce_and_key_are_separated() {
+    let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+    K12Stream::new(b"key", b"nonce").read(&mut a);
+    K12Stream::new(b"keyn", b"once").read(&mut b);
+    assert_ne!(a, b);
+}
@@ -0,0 +1,84 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, Reset, Update, XofReader},
+    K12Tuple, KangarooTwelve,
+};
+
+mod common;
+use common::ptn;
+
+fn tuple(items: &[&[u8]], customization: &[u8]) -> Box<[u8]> {
+    let mut hasher = K12Tuple::new_with_customization(customization);
+    for item in items {
+        hasher.absorb_item(item);
+    }
+    let mut output = [0u8; 32];
+    hasher.finalize_xof_into(&mut output);
+    Box::new(output)
+}
+
+#[test]
+#[rustfmt::skip]
+fn tuple_vectors() {
+    assert_eq!(
+        tuple(&[b"ab", b"c"], b"")[..],
+        hex!("6e4ff2b8bd98471f7836c310180312d45d5d1da62b8b837b8de40a75a16566fa")[..]
+    );
+    assert_eq!(
+        tuple(&[b"a", b"bc"], b"")[..],
+        hex!("5d5b5366ddad15ad35f06ea3c5ce9e7dd7fe66bae35783d5e42566746c3491ea")[..]
+    );
+    assert_eq!(
+        tuple(&[], b"")[..],
+        hex!("e3b35aa433eae55790eaed81458f1fd33f82a60cc735cabd0cf7a014e1e000f9")[..]
+    );
+    assert_eq!(
+        tuple(&[b""], b"")[..],
+        hex!("e3f24a19bb607f5b7e756a1900072b1086efa888ebb8488f63bebf009a2ed4f4")[..]
+    );
+    assert_eq!(
+        tuple(&[b"ab", b"c"], b"domain")[..],
+        hex!("c00d806cbba0fc90483f629b0b6f0b3870a1ba6b1562441eed9dbcafda8b4bd0")[..]
+    );
+    assert_eq!(
+        tuple(&[&ptn(10_000), b"", &ptn(17usize.pow(3))], b"")[..],
+        hex!("d220a2a4ef84f440c3bd8f74b0b66bf63637ed04ba75352172a09f4ec18a77e9")[..]
+    );
+}
+
+#[test]
+fn tuple_is_not_concatenation() {
+    let concatenation = KangarooTwelve::new().chain(b"abc").finalize_boxed(32);
+    assert_ne!(tuple(&[b"abc"], b""), concatenation);
+    assert_ne!(tuple(&[b"abc", b""], b""), tuple(&[b"abc"], b""));
+}
+
+#[test]
+fn tuple_reset() {
+    let mut hasher = K12Tuple::new_with_customization(b"domain");
+    hasher.absorb_item(b"something else");
+    hasher.reset();
+    hasher.absorb_item(b"ab");
+    hasher.absorb_item(b"c");
+    let mut first = [0u8; 32];
+    hasher.finalize_xof_reset().read(&mut first);
+    assert_eq!(first[..], tuple(&[b"ab", b"c"], b"domain")[..]);
+
+    let mut second = [0u8; 32];
+    hasher
+        .chain_item(b"ab")
+        .chain_item(b"c")
+        .finalize_xof_into(&mut second);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn tuple_from_hasher() {
+    let hasher = KangarooTwelve::new_with_customization(b"domain").chain(b"discarded");
+    let mut output = [0u8; 32];
+    K12Tuple::from(hasher)
+        .chain_item(b"ab")
+        .chain_item(b"c")
+        .finalize_xof_into(&mut output);
+    assert_eq!(output[..], tuple(&[b"ab", b"c"], b"domain")[..]);
+}
@@ -0,0 +1,157 @@
+use hex_literal::hex;
+use k12::{
+    digest::{ExtendableOutput, ExtendableOutputReset, Update},
+    InvalidDomainSeparation, TurboShake128, TurboShake256,
+};
+
+mod common;
+use common::ptn;
+
+fn turbo_shake128(data: &[u8], d: u8, n: usize) -> Box<[u8]> {
+    TurboShake128::new(d).unwrap().chain(data).finalize_boxed(n)
+}
+
+fn turbo_shake256(data: &[u8], d: u8, n: usize) -> Box<[u8]> {
+    TurboShake256::new(d).unwrap().chain(data).finalize_boxed(n)
+}
+
+#[test]
+#[rustfmt::skip]
+fn turbo_shake128_vectors() {
+    // Source: RFC 9861, section 5
+    assert_eq!(
+        turbo_shake128(b"", 0x1F, 64)[..],
+        hex!("
+            1e415f1c5983aff2169217277d17bb538cd945a397ddec541f1ce41af2c1b74c
+            3e8ccae2a4dae56c84a04c2385c03c15e8193bdf58737363321691c05462c8df
+        ")[..]
+    );
+    assert_eq!(
+        turbo_shake128(b"", 0x1F, 10032)[10000..],
+        hex!("a3b9b0385900ce761f22aed548e754da10a5242d62e8c658e3f3a923a7555607")[..]
+    );
+
+    let expected = [
+        hex!("55cedd6f60af7bb29a4042ae832ef3f58db7299f893ebb9247247d856958daa9"),
+        hex!("9c97d036a3bac819db70ede0ca554ec6e4c2a1a4ffbfd9ec269ca6a111161233"),
+        hex!("96c77c279e0126f7fc07c9b07f5cdae1e0be60bdbe10620040e75d7223a624d2"),
+        hex!("d4976eb56bcf118520582b709f73e1d6853e001fdaf80e1b13e0d0599d5fb372"),
+    ];
+    for (i, expected) in expected.iter().enumerate() {
+        let m = ptn(17usize.pow(i as u32));
+        assert_eq!(turbo_shake128(&m, 0x1F, 32)[..], expected[..]);
+    }
+
+    let expected = [
+        (0x01, hex!("bf323f940494e88ee1c540fe660be8a0c93f43d15ec006998462fa994eed5dab")),
+        (0x06, hex!("3d03988bb59e681851a192f429ae03988e8f444bc06036a3f1a7d2ccd758d174")),
+        (0x07, hex!("b658576001cad9b1e5f399a9f77723bba05458042d68206f7252682dba3663ed")),
+        (0x0B, hex!("a59658332b8e13c455a21a25300379d2012c5c6aba6aabfa6c50b28bfc01601f")),
+        (0x30, hex!("05f3ae2021e14c19f72dd588a79bf39ecbf964be12742f14fecf775e0d90bf74")),
+        (0x7F, hex!("16274cc656d44cefd422395d0f9053bda6d28e122aba15c765e5ad0e6eaf26f9")),
+    ];
+    for &(d, expected) in expected.iter() {
+        assert_eq!(turbo_shake128(&[0xFF; 3], d, 32)[..], expected[..], "{:#04x}", d);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn turbo_shake256_vectors() {
+    // Source: RFC 9861, section 5
+    assert_eq!(
+        turbo_shake256(b"", 0x1F, 64)[..],
+        hex!("
+            367a329dafea871c7802ec67f905ae13c57695dc2c6663c61035f59a18f8e7db
+            11edc0e12e91ea60eb6b32df06dd7f002fbafabb6e13ec1cc20d995547600db0
+        ")[..]
+    );
+    assert_eq!(
+        turbo_shake256(b"", 0x1F, 10032)[10000..],
+        hex!("abefa11630c661269249742685ec082f207265dccf2f43534e9c61ba0c9d1d75")[..]
+    );
+
+    let expected = [
+        hex!("
+            3e1712f928f8eaf1054632b2aa0a246ed8b0c378728f60bc970410155c28820e
+            90cc90d8a3006aa2372c5c5ea176b0682bf22bae7467ac94f74d43d39b0482e2
+        "),
+        hex!("
+            b3bab0300e6a191fbe6137939835923578794ea54843f5011090fa2f3780a9e5
+            cb22c59d78b40a0fbff9e672c0fbe0970bd2c845091c6044d687054da5d8e9c7
+        "),
+        hex!("
+            66b810db8e90780424c0847372fdc95710882fde31c6df75beb9d4cd9305cfca
+            e35e7b83e8b7e6eb4b78605880116316fe2c078a09b94ad7b8213c0a738b65c0
+        "),
+        hex!("
+            c74ebc919a5b3b0dd1228185ba02d29ef442d69d3d4276a93efe0bf9a16a7dc0
+            cd4eabadab8cd7a5edd96695f5d360abe09e2c6511a3ec397da3b76b9e1674fb
+        "),
+    ];
+    for (i, expected) in expected.iter().enumerate() {
+        let m = ptn(17usize.pow(i as u32));
+        assert_eq!(turbo_shake256(&m, 0x1F, 64)[..], expected[..]);
+    }
+
+    let expected = [
+        (0x01, hex!("
+            d21c6fbbf587fa2282f29aea620175fb0257413af78a0b1b2a87419ce031d933
+            ae7a4d383327a8a17641a34f8a1d1003ad7da6b72dba84bb62fef28f62f12424
+        ")),
+        (0x06, hex!("
+            e5538cdd28302a2e81e41f65fd2a4052014d0cd463df671d1e510a9d95c37d71
+            35ef2728430a9e317004f836c9a238ef35370280d03dce7f0612f0315b3cbf63
+        ")),
+        (0x07, hex!("
+            18b3b5b7061c2e67c1753a00e6ad7ed7ba1c906cf93efb7092eaf27fbeebb755
+            ae6e292493c110e48d260028492b8e09b5500612b8f2578985ded5357d00ec67
+        ")),
+        (0x0B, hex!("
+            a238ed4e0672e5e5309e06ba33ac2bef5b6f4cf22ea8411841cf0fb9626cfa2c
+            e1a4a78288c3942b1abf04f672e9d2e579efdc1e4aee4cbe4c8e4c1dd0ac07f6
+        ")),
+        (0x30, hex!("
+            81a60e224edd289f922fde87f0dfe717fc0c9c862318875b09de74e517821fa3
+            6685b74c823b2d1f4a83c851c8ae8d3f11cd43f72b89ad23638e41b74c1f87fc
+        ")),
+        (0x7F, hex!("
+            abe569c1f77ec340f02705e7d37c9ab7e155516e4a6a150021d70b6fac0bb40c
+            069f9a9828a0d575cd99f9bae435ab1acf7ed9110ba97ce0388d074bac768776
+        ")),
+    ];
+    for &(d, expected) in expected.iter() {
+        assert_eq!(turbo_shake256(&[0xFF; 3], d, 64)[..], expected[..], "{:#04x}", d);
+    }
+}
+
+#[test]
+fn domain_separation_range() {
+    for &d in &[0x00, 0x80, 0xFF] {
+        assert_eq!(
+            TurboShake128::new(d).unwrap_err(),
+            InvalidDomainSeparation(d)
+        );
+        assert_eq!(
+            TurboShake256::new(d).unwrap_err(),
+            InvalidDomainSeparation(d)
+        );
+    }
+    for &d in &[0x01, 0x1F, 0x7F] {
+        assert_eq!(TurboShake128::new(d).unwrap().domain_separation(), d);
+        assert_eq!(TurboShake256::new(d).unwrap().domain_separation(), d);
+    }
+}
+
+#[test]
+fn incremental_and_reset() {
+    let m = ptn(17usize.pow(3));
+    let expected = turbo_shake128(&m, 0x30, 32);
+    let mut xof = TurboShake128::new(0x30).unwrap();
+    for piece in m.chunks(47) {
+        xof.update(piece);
+    }
+    assert_eq!(xof.finalize_boxed_reset(32), expected);
+    xof.update(&m);
+    assert_eq!(xof.finalize_boxed(32), expected);
+}